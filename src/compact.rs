@@ -0,0 +1,95 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// A short text form of the raw bits, for storing wide masks where a list of
+/// flag names would be too long.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    let encode = cfg!(feature = "alloc").then(|| {
+        quote! {
+            const _: () = {
+                extern crate alloc;
+
+                impl #bits_struct_name {
+                    /// Encodes the bits as unpadded URL-safe base64 of their
+                    /// big-endian bytes without leading zero bytes, which fits
+                    /// headers and cookies. The empty value encodes as `""`.
+                    #inline
+                    pub fn to_compact_string(self) -> alloc::string::String {
+                        const ALPHABET: &[u8; 64] =
+                            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+                        let bytes = self.0.to_be_bytes();
+                        let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+                        let bytes = &bytes[start..];
+
+                        let mut encoded = alloc::string::String::with_capacity((bytes.len() * 4).div_ceil(3));
+                        for chunk in bytes.chunks(3) {
+                            let mut group = 0u32;
+                            for (i, &byte) in chunk.iter().enumerate() {
+                                group |= (byte as u32) << (16 - 8 * i);
+                            }
+                            for i in 0..=chunk.len() {
+                                encoded.push(ALPHABET[(group >> (18 - 6 * i)) as usize & 63] as char);
+                            }
+                        }
+                        encoded
+                    }
+                }
+            };
+        }
+    });
+
+    quote! {
+        impl #bits_struct_name {
+            /// Decodes the text written by `to_compact_string`, keeping bits
+            /// not covered by any flag. Returns `None` for invalid base64 or
+            /// values wider than the representation.
+            #inline
+            pub fn from_compact_string(s: &str) -> Option<Self> {
+                let mut bits: #bits_type = 0;
+                let mut buffer = 0u32;
+                let mut buffered = 0;
+
+                for c in s.bytes() {
+                    let digit = match c {
+                        b'A'..=b'Z' => c - b'A',
+                        b'a'..=b'z' => c - b'a' + 26,
+                        b'0'..=b'9' => c - b'0' + 52,
+                        b'-' => 62,
+                        b'_' => 63,
+                        _ => return None,
+                    };
+                    buffer = (buffer << 6 | digit as u32) & 0xfff;
+                    buffered += 6;
+
+                    if buffered >= 8 {
+                        buffered -= 8;
+                        if bits >> (#bits_type::BITS - 8) != 0 {
+                            return None;
+                        }
+                        // Shifting a `u8` by 8 overflows, it is 0 here in that case
+                        bits = bits.checked_shl(8).unwrap_or(0) | ((buffer >> buffered) & 0xff) as #bits_type;
+                    }
+                }
+
+                // A lone trailing character or set padding bits are not
+                // written by `to_compact_string`
+                if buffered >= 6 || buffer & ((1 << buffered) - 1) != 0 {
+                    return None;
+                }
+                Some(Self(bits))
+            }
+        }
+
+        #encode
+    }
+}
@@ -1,147 +1,1404 @@
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{Ident, ItemEnum, Visibility};
-
-pub fn derive(input: &ItemEnum, vis: &Visibility, name: &Ident, bits_type: &Ident) -> TokenStream {
-    let bits_struct_name = Ident::new(&format!("{}Bits", name), name.span());
-    let variant_idents: Vec<_> = input.variants.iter().map(|v| &v.ident).collect();
-    let variant_names: Vec<String> = input.variants.iter().map(|v| v.ident.to_string()).collect();
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
+use syn::{
+    Expr, Ident, ItemEnum, LitStr, Meta, Path, Token, Variant, Visibility, punctuated::Punctuated,
+    token,
+};
+
+use crate::{
+    BitmaskOptions, EnumDebug, Inline, Order, Radix, ValidBits, VariantOptions, compact,
+    eval::{eval, repr_width},
+    extends, ffi, fields, groups, interop, map, names,
+    resolve_masks::union_expr,
+    subset, tracked, usage,
+};
+
+/// Names shared by the pieces of generated code.
+pub struct Context<'a> {
+    pub vis: &'a Visibility,
+    pub name: &'a Ident,
+    pub bits_struct_name: Ident,
+    pub error_name: Ident,
+    /// The integer type wrapped by the bits type.
+    pub bits_type: &'a Path,
+    /// The `repr` of the enum, the same type as `bits_type` unless that is
+    /// set with `#[bitmask(bits_type = ...)]`.
+    pub repr: &'a Ident,
+    pub inline: TokenStream,
+    pub unknown_radix: Radix,
+    /// Every variant, including skipped ones.
+    pub variants: Vec<&'a Ident>,
+    /// Variants that show up in the generated flag listings.
+    pub listed: Vec<&'a Ident>,
+    /// Listed variants that are accepted by name when parsing, leaving out
+    /// deprecated ones unless `include_deprecated` is set.
+    pub parsed: Vec<&'a Ident>,
+    #[cfg_attr(not(any(feature = "enumflags2", feature = "prost")), allow(dead_code))]
+    pub options: &'a BitmaskOptions,
+    /// The registry type set with `#[bitmask(register = ...)]`.
+    #[cfg_attr(not(feature = "inventory"), allow(dead_code))]
+    pub register: Option<&'a Path>,
+}
+
+pub fn derive(
+    input: &ItemEnum,
+    vis: &Visibility,
+    name: &Ident,
+    repr: &Ident,
+    variant_options: &[VariantOptions],
+    options: &BitmaskOptions,
+) -> TokenStream {
+    let variants: Vec<_> = input.variants.iter().zip(variant_options).collect();
+    let bits_type = &options
+        .bits_type
+        .clone()
+        .unwrap_or_else(|| Path::from(repr.clone()));
+    let cx = Context {
+        vis,
+        name,
+        bits_struct_name: Ident::new(&format!("{}Bits", name), name.span()),
+        error_name: Ident::new(&format!("{}BitsError", name), name.span()),
+        bits_type,
+        repr,
+        inline: match options.inline {
+            Inline::Always => quote!(#[inline(always)]),
+            Inline::Hint => quote!(#[inline]),
+            Inline::Never => quote!(#[inline(never)]),
+        },
+        unknown_radix: options.unknown_radix,
+        variants: variants.iter().map(|(v, _)| &v.ident).collect(),
+        // Skipped variants still get their value, but are left out of every
+        // generated listing of the flags.
+        listed: variants
+            .iter()
+            .filter(|(_, o)| !o.skip)
+            .map(|(v, _)| &v.ident)
+            .collect(),
+        parsed: variants
+            .iter()
+            .filter(|(_, o)| !o.skip && (!o.deprecated || options.include_deprecated))
+            .map(|(v, _)| &v.ident)
+            .collect(),
+        options,
+        register: options.register.as_ref(),
+    };
+    let Context {
+        vis,
+        name,
+        bits_struct_name,
+        error_name,
+        bits_type,
+        repr,
+        inline,
+        unknown_radix,
+        variants: all_variants,
+        listed: variant_idents,
+        ..
+    } = &cx;
+    let variant_names: Vec<String> = variant_idents.iter().map(|i| i.to_string()).collect();
+    // Variants are referenced by path, as the value of a variant depending on
+    // a `cfg_attr` compound differs between configurations.
+    let discriminants = |filter: &dyn Fn(&VariantOptions) -> bool| {
+        let values: Vec<Expr> = variants
+            .iter()
+            .filter(|(_, o)| filter(o))
+            .map(|(v, _)| {
+                let ident = &v.ident;
+                syn::parse_quote!(#name::#ident as #bits_type)
+            })
+            .collect();
+        union_expr(values.iter())
+    };
+    let all = discriminants(&|o| !o.skip && (!o.deprecated || options.include_deprecated));
+    let deprecated = discriminants(&|o| o.deprecated);
+
+    let (valid_const, valid_name) = match options.valid_bits {
+        ValidBits::All => (quote!(ALL), "`ALL`"),
+        ValidBits::Mask => (quote!(MASK), "`MASK`"),
+    };
+    let verbose_debug = matches!(options.enum_debug, EnumDebug::Verbose);
+    let enum_item = with_derives(&with_cfg_variants(input, variant_options), verbose_debug);
+    let unknown_format = match unknown_radix {
+        Radix::Bin => "{:#b}",
+        Radix::Hex => "{:#x}",
+        Radix::Dec => "{}",
+    };
+    let display_name = match &options.display_with {
+        Some(display_with) => quote! {
+            if display {
+                #display_with(flag, f)?
+            } else {
+                f.write_str(flag_name)?
+            }
+        },
+        None => quote!(f.write_str(flag_name)?),
+    };
+    let iter_name = format_ident!("{}Iter", bits_struct_name);
+    let flag_count = variant_idents.len();
+    let flag_order = match options.order_by {
+        Order::Decl => quote!([#((#name::#variant_idents, #variant_names)),*]),
+        Order::Bit => quote!(Self::SORTED_FLAGS),
+    };
+    let mut struct_docs = struct_docs(name, repr, &variants);
+    if options.stable_abi {
+        struct_docs.push(String::new());
+        struct_docs.push(format!(
+            " The layout is part of the stable ABI: `#[repr(transparent)]` over the `{}` it wraps, with the same size and alignment.",
+            bits_type.to_token_stream().to_string().replace(' ', "")
+        ));
+    }
+    let doc_aliases = doc_aliases(&variants);
+    let bits_attrs = &options.bits_attrs;
+
+    let mask_impl = options.mask.as_ref().map(|mask| {
+        let checks = all_variants.iter().map(|ident| {
+            let message = format!("variant `{ident}` has bits outside of the bitmask `mask`");
+            quote_spanned! {ident.span()=>
+                assert!((#name::#ident as #bits_type) & !#bits_struct_name::MASK.0 == 0, #message);
+            }
+        });
+
+        quote! {
+            impl #bits_struct_name {
+                /// The region of the underlying integer that holds valid bits.
+                pub const MASK: Self = Self(#mask);
+            }
+
+            #[allow(deprecated)]
+            const _: () = {
+                #(#checks)*
+            };
+        }
+    });
+
+    // `mask` widens the accepted bits from the flags to the declared region
+    let (valid, valid_doc) = match options.mask {
+        Some(_) => (quote!(Self::MASK), "[`Self::MASK`]"),
+        None => (quote!(Self::ALL), "[`Self::ALL`]"),
+    };
+    let from_bits_doc =
+        format!(" Returns `None` if `bits` has any bits set outside of {valid_doc}.");
+    let from_bits_truncate_doc = format!(" Clears every bit outside of {valid_doc}.");
+    let truncate_doc = format!(
+        " Clears every bit of `self` outside of {valid_doc}, like [`Self::from_bits_truncate`]."
+    );
+
+    let bits_type_checks = options.bits_type.as_ref().map(|_| {
+        let checks = all_variants.iter().map(|ident| {
+            let message = format!("variant `{ident}` does not fit into the bitmask `bits_type`");
+            quote_spanned! {ident.span()=>
+                assert!(#name::#ident as #bits_type as #repr == #name::#ident as #repr, #message);
+            }
+        });
+
+        quote! {
+            #[allow(deprecated)]
+            const _: () = {
+                #(#checks)*
+            };
+        }
+    });
+
+    let validate_checks = options.validate.as_ref().map(|validate| {
+        let checks = all_variants.iter().map(|ident| {
+            let message =
+                format!("variant `{ident}` is rejected by the bitmask `validate` function");
+            quote_spanned! {ident.span()=>
+                assert!(#validate(#name::#ident as #repr), #message);
+            }
+        });
+
+        quote! {
+            #[allow(deprecated)]
+            const _: () = {
+                #(#checks)*
+            };
+        }
+    });
+
+    let combinations_impl = options.all_combinations.then(|| {
+        quote! {
+            impl #bits_struct_name {
+                /// Iterates over every combination of the listed single-bit
+                /// flags in ascending order, starting with the empty value.
+                /// With `N` such flags, this yields `2^N` values.
+                #inline
+                pub fn all_combinations() -> impl Iterator<Item = Self> {
+                    let mut singles: #bits_type = 0;
+                    for (flag, _) in Self::FLAG_ORDER {
+                        let bits = flag as #bits_type;
+                        if bits.count_ones() == 1 {
+                            singles |= bits;
+                        }
+                    }
+
+                    // The next larger subset of `singles`, wrapping to 0 after
+                    // the last one
+                    core::iter::successors(Some(0), move |&bits: &#bits_type| {
+                        let next = (bits | !singles).wrapping_add(1) & singles;
+                        (next != 0).then_some(next)
+                    })
+                    .map(Self)
+                }
+            }
+        }
+    });
+
+    let default_impl = (!options.default_values.is_empty()).then(|| {
+        let defaults = options.default_values.iter().map(|value| {
+            let (cfg, expr) = (&value.cfg, &value.expr);
+            quote! {
+                #[cfg(all(#(#cfg),*))]
+                #[allow(deprecated)]
+                pub const DEFAULT: Self = Self(#expr);
+            }
+        });
+
+        quote! {
+            impl #bits_struct_name {
+                #(
+                    /// The value returned by `Default`, set with
+                    /// `#[bitmask(default = ...)]`.
+                    #defaults
+                )*
+
+                /// Sets the value back to [`Self::DEFAULT`].
+                #inline
+                pub fn reset(&mut self) {
+                    *self = Self::DEFAULT;
+                }
+            }
+
+            impl core::default::Default for #bits_struct_name {
+                #inline
+                fn default() -> Self {
+                    Self::DEFAULT
+                }
+            }
+        }
+    });
+
+    let extends_callback = extends::callback(vis, name, repr, &variants);
+    let extends_impl = options
+        .extends
+        .as_ref()
+        .zip(options.inherited.as_ref())
+        .map(|(base, inherited)| {
+            extends::conversions(
+                base,
+                inherited,
+                name,
+                bits_struct_name,
+                error_name,
+                repr,
+                inline,
+            )
+        });
+    let alloc_impl = cfg!(feature = "alloc").then(|| {
+        quote! {
+            const _: () = {
+                extern crate alloc;
+
+                impl core::convert::From<#bits_struct_name> for alloc::vec::Vec<#name> {
+                    /// Lists the contained flags in the order set by `order_by`.
+                    #inline
+                    fn from(bits: #bits_struct_name) -> Self {
+                        bits.to_vec()
+                    }
+                }
+
+                impl core::convert::From<&alloc::collections::BTreeSet<#name>> for #bits_struct_name {
+                    #inline
+                    fn from(set: &alloc::collections::BTreeSet<#name>) -> Self {
+                        set.iter().fold(Self(0), |bits, &flag| bits | Self::from(flag))
+                    }
+                }
+
+                impl #bits_struct_name {
+                    /// Lists the contained flags, including compounds, in the
+                    /// order set by `order_by`, e.g. before serializing them.
+                    #inline
+                    pub fn to_vec(self) -> alloc::vec::Vec<#name> {
+                        self.iter().collect()
+                    }
+
+                    /// Collects the contained flags, including compounds, into
+                    /// a `BTreeSet`.
+                    #inline
+                    pub fn to_btree_set(self) -> alloc::collections::BTreeSet<#name> {
+                        let mut flags = alloc::collections::BTreeSet::new();
+                        self.for_each_flag(|flag| {
+                            flags.insert(flag);
+                        });
+                        flags
+                    }
+                }
+            };
+        }
+    });
+    let std_impl = cfg!(feature = "std").then(|| {
+        quote! {
+            const _: () = {
+                extern crate std;
+
+                impl<S> core::convert::From<&std::collections::HashSet<#name, S>> for #bits_struct_name {
+                    #inline
+                    fn from(set: &std::collections::HashSet<#name, S>) -> Self {
+                        set.iter().fold(Self(0), |bits, &flag| bits | Self::from(flag))
+                    }
+                }
+
+                impl #bits_struct_name {
+                    /// Collects the contained flags, including compounds, into
+                    /// a `HashSet`.
+                    #inline
+                    pub fn to_hash_set(self) -> std::collections::HashSet<#name> {
+                        let mut flags = std::collections::HashSet::new();
+                        self.for_each_flag(|flag| {
+                            flags.insert(flag);
+                        });
+                        flags
+                    }
+                }
+            };
+        }
+    });
+    let tracked_impl = options.tracked.then(|| tracked::impls(&cx));
+    let usage_impl = options.report_usage.then(|| usage::impls(&cx, &variants));
+    let enum_debug_impl = verbose_debug.then(|| {
+        quote! {
+            impl core::fmt::Debug for #name {
+                #inline
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.write_str(self.name())?;
+                    f.write_str(" (")?;
+                    write!(f, #unknown_format, *self as #bits_type)?;
+                    f.write_str(")")
+                }
+            }
+        }
+    });
+    let compact_impl = compact::impls(&cx);
+    let ffi_impl = ffi::impls(&cx);
+    let stable_abi_impl = options.stable_abi.then(|| ffi::stable_abi(&cx));
+    let names_impl = names::impls(&cx);
+    let map_impl = map::impls(&cx);
+    let fields_impl = fields::impls(&cx, &variants);
+    let groups_impl = groups::impls(&cx, &variants);
+    let interop_impl = interop::impls(&cx);
+    let subset_impl = options
+        .subset_of
+        .as_ref()
+        .map(|parent| subset::conversions(parent, name, bits_struct_name, repr, inline));
+
+    // The same operators with the enum taken by reference, for iterators
+    // yielding `&Permissions`
+    let ref_ops = [
+        (quote!(BitOr), quote!(bitor), quote!(|)),
+        (quote!(BitAnd), quote!(bitand), quote!(&)),
+        (quote!(BitXor), quote!(bitxor), quote!(^)),
+    ]
+    .map(|(op_trait, method, op)| {
+        quote! {
+            impl core::ops::#op_trait<&#name> for #name {
+                type Output = #bits_struct_name;
+                #inline
+                fn #method(self, rhs: &#name) -> #bits_struct_name {
+                    #bits_struct_name(self.bits() #op rhs.bits())
+                }
+            }
+
+            impl core::ops::#op_trait<#name> for &#name {
+                type Output = #bits_struct_name;
+                #inline
+                fn #method(self, rhs: #name) -> #bits_struct_name {
+                    #bits_struct_name(self.bits() #op rhs.bits())
+                }
+            }
+
+            impl core::ops::#op_trait<&#name> for &#name {
+                type Output = #bits_struct_name;
+                #inline
+                fn #method(self, rhs: &#name) -> #bits_struct_name {
+                    #bits_struct_name(self.bits() #op rhs.bits())
+                }
+            }
+        }
+    });
+    let ref_assign_ops = [
+        (
+            quote!(BitOrAssign),
+            quote!(bitor_assign),
+            quote!(self.0 |= rhs.bits()),
+        ),
+        (
+            quote!(BitAndAssign),
+            quote!(bitand_assign),
+            quote!(self.0 &= rhs.bits()),
+        ),
+        (
+            quote!(BitXorAssign),
+            quote!(bitxor_assign),
+            quote!(self.0 ^= rhs.bits()),
+        ),
+        (
+            quote!(SubAssign),
+            quote!(sub_assign),
+            quote!(self.0 &= !rhs.bits()),
+        ),
+    ]
+    .map(|(op_trait, method, body)| {
+        quote! {
+            impl core::ops::#op_trait<&#name> for #bits_struct_name {
+                #inline
+                fn #method(&mut self, rhs: &#name) {
+                    #body;
+                }
+            }
+        }
+    });
+
     let expanded = quote! {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-    #input
+    #enum_item
 
+    #(#[doc = #struct_docs])*
+    #(#[doc(alias = #doc_aliases)])*
+    #(#bits_attrs)*
     #[repr(transparent)]
+    #[must_use]
     #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     #vis struct #bits_struct_name ( #bits_type );
 
     impl core::ops::BitOrAssign for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitor_assign(&mut self, rhs: Self) {
             self.0 |= rhs.0;
         }
     }
 
     impl core::ops::BitAndAssign for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitand_assign(&mut self, rhs: Self) {
             self.0 &= rhs.0;
         }
     }
 
     impl core::ops::BitXorAssign for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitxor_assign(&mut self, rhs: Self) {
             self.0 ^= rhs.0;
         }
     }
 
     impl core::ops::SubAssign for #bits_struct_name {
-        #[inline]
+        #inline
         fn sub_assign(&mut self, rhs: Self) {
             self.0 &= !rhs.0;
         }
     }
 
     impl core::ops::BitOrAssign<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitor_assign(&mut self, rhs: #name) {
             self.0 |= rhs.bits();
         }
     }
 
     impl core::ops::BitAndAssign<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitand_assign(&mut self, rhs: #name) {
             self.0 &= rhs.bits();
         }
     }
 
     impl core::ops::BitXorAssign<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn bitxor_assign(&mut self, rhs: #name) {
             self.0 ^= rhs.bits();
         }
     }
 
     impl core::ops::SubAssign<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn sub_assign(&mut self, rhs: #name) {
             self.0 &= !rhs.bits();
         }
     }
 
     impl core::convert::From<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn from(val: #name) -> Self {
             Self(val.bits())
         }
     }
 
+    #[doc = concat!(" Iterator over the flags contained in a [`", stringify!(#bits_struct_name), "`], created by its `iter` method.")]
+    #[derive(Clone, Debug)]
+    #vis struct #iter_name {
+        bits: #bits_struct_name,
+        index: usize,
+    }
+
+    impl Iterator for #iter_name {
+        type Item = #name;
+
+        #inline
+        fn next(&mut self) -> Option<#name> {
+            while let Some(&(flag, _)) = #bits_struct_name::FLAG_ORDER.get(self.index) {
+                self.index += 1;
+                let bits = flag as #bits_type;
+                if bits != 0 && self.bits.0 & bits == bits {
+                    return Some(flag);
+                }
+            }
+            None
+        }
+    }
+
+    impl core::iter::FusedIterator for #iter_name {}
+
+    impl IntoIterator for #bits_struct_name {
+        type Item = #name;
+        type IntoIter = #iter_name;
+
+        #inline
+        fn into_iter(self) -> #iter_name {
+            self.iter()
+        }
+    }
+
+    impl IntoIterator for &#bits_struct_name {
+        type Item = #name;
+        type IntoIter = #iter_name;
+
+        #inline
+        fn into_iter(self) -> #iter_name {
+            self.iter()
+        }
+    }
+
+    impl core::iter::FromIterator<#name> for #bits_struct_name {
+        #inline
+        fn from_iter<I: IntoIterator<Item = #name>>(iter: I) -> Self {
+            let mut bits = Self(0);
+            bits.extend(iter);
+            bits
+        }
+    }
+
+    impl core::iter::Extend<#name> for #bits_struct_name {
+        #inline
+        fn extend<I: IntoIterator<Item = #name>>(&mut self, iter: I) {
+            for flag in iter {
+                self.0 |= flag.bits();
+            }
+        }
+    }
+
     impl core::convert::From<#bits_struct_name> for #bits_type {
-        #[inline]
+        #inline
         fn from(val: #bits_struct_name) -> Self {
             val.0
         }
     }
 
-    impl core::convert::From<#bits_type> for #bits_struct_name {
-        #[inline]
-        fn from(val: #bits_type) -> Self {
-            Self(val)
+    impl core::convert::TryFrom<#bits_type> for #bits_struct_name {
+        type Error = #error_name;
+
+        #inline
+        fn try_from(val: #bits_type) -> Result<Self, Self::Error> {
+            let undefined = val & !Self::ALL.0;
+            if undefined == 0 {
+                Ok(Self(val))
+            } else {
+                Err(#error_name(undefined))
+            }
+        }
+    }
+
+    /// Error returned when converting an integer with bits that are not
+    /// covered by any flag.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #vis struct #error_name(#bits_type);
+
+    impl #error_name {
+        /// Returns the bits that are not covered by any flag.
+        #inline
+        pub const fn undefined_bits(&self) -> #bits_type {
+            self.0
+        }
+    }
+
+    impl core::fmt::Display for #error_name {
+        #inline
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, concat!("undefined ", stringify!(#name), " bits: {:#x}"), self.0)
         }
     }
 
+    impl core::error::Error for #error_name {}
+
     impl core::convert::From<#name> for #bits_type {
-        #[inline]
+        #inline
         fn from(val: #name) -> Self {
             val.bits()
         }
     }
 
 
+    impl #bits_struct_name {
+        /// No flags set.
+        pub const EMPTY: Self = Self(0);
+
+        /// The union of all flags.
+        #[allow(deprecated)]
+        pub const ALL: Self = Self(#all);
+
+        /// The union of all flags marked `#[deprecated]`.
+        #[allow(deprecated)]
+        pub const DEPRECATED_MASK: Self = Self(#deprecated);
+
+        /// The width of the bits type, in bits.
+        pub const BITS: u32 = #bits_type::BITS;
+
+        #[doc = #from_bits_doc]
+        #inline
+        pub const fn from_bits(bits: #bits_type) -> Option<Self> {
+            if bits & !#valid.0 == 0 {
+                Some(Self(bits))
+            } else {
+                None
+            }
+        }
+
+        #[doc = #from_bits_truncate_doc]
+        #inline
+        pub const fn from_bits_truncate(bits: #bits_type) -> Self {
+            Self(bits & #valid.0)
+        }
+
+        /// Wraps `bits` as is, keeping any bits that are not covered by a flag.
+        #inline
+        pub const fn from_bits_retain(bits: #bits_type) -> Self {
+            Self(bits)
+        }
+
+        /// Wraps `bits` as is, the same as [`Self::from_bits_retain`].
+        #inline
+        pub const fn new(bits: #bits_type) -> Self {
+            Self(bits)
+        }
+
+        /// The raw value, including bits that are not covered by a flag.
+        #inline
+        pub const fn bits(self) -> #bits_type {
+            self.0
+        }
+
+        /// The raw value widened to `u128`, for code handling bitmasks of
+        /// different widths through a single integer type.
+        #inline
+        pub const fn bits_u128(self) -> u128 {
+            self.0 as u128
+        }
+
+        /// Like [`Self::from_bits_truncate`] for a `u128`, also dropping the
+        /// bits beyond the width of the bits type.
+        #inline
+        pub const fn from_u128_truncate(bits: u128) -> Self {
+            Self::from_bits_truncate(bits as #bits_type)
+        }
+
+        /// The union of `values`, e.g. to combine a static table of masks
+        /// into a constant. An empty slice gives the empty value.
+        #inline
+        pub const fn union_all(values: &[Self]) -> Self {
+            let mut bits: #bits_type = 0;
+            let mut i = 0;
+            while i < values.len() {
+                bits |= values[i].0;
+                i += 1;
+            }
+            Self(bits)
+        }
+
+        /// The union of `flags`, to declare static masks from slices of
+        /// variants. An empty slice gives the empty value.
+        #inline
+        pub const fn union_of(flags: &[#name]) -> Self {
+            let mut bits: #bits_type = 0;
+            let mut i = 0;
+            while i < flags.len() {
+                bits |= flags[i] as #bits_type;
+                i += 1;
+            }
+            Self(bits)
+        }
+
+        /// The intersection of `values`, the bits set in every one of them.
+        /// An empty slice gives [`Self::ALL`].
+        #inline
+        pub const fn intersect_all(values: &[Self]) -> Self {
+            let [first, rest @ ..] = values else {
+                return Self::ALL;
+            };
+            let mut bits = first.0;
+            let mut i = 0;
+            while i < rest.len() {
+                bits &= rest[i].0;
+                i += 1;
+            }
+            Self(bits)
+        }
+
+        /// Whether every bit of `other` is set.
+        #inline
+        pub fn contains(self, other: impl Into<Self>) -> bool {
+            let other = other.into().0;
+            self.0 & other == other
+        }
+
+        /// Whether `flag` is set, e.g. for binding a checkbox together with
+        /// [`Self::set`].
+        #inline
+        pub const fn get(self, flag: #name) -> bool {
+            let bits = flag as #bits_type;
+            self.0 & bits == bits
+        }
+
+        /// Whether any bit of `other` is set.
+        #inline
+        pub fn intersects(self, other: impl Into<Self>) -> bool {
+            self.0 & other.into().0 != 0
+        }
+
+        /// The number of listed single-bit flags contained in `self`.
+        /// Compounds and bits not covered by a flag are not counted.
+        #inline
+        pub const fn len(self) -> usize {
+            let mut count = 0;
+            let mut i = 0;
+            while i < Self::FLAG_ORDER.len() {
+                let bits = Self::FLAG_ORDER[i].0 as #bits_type;
+                if bits.count_ones() == 1 && self.0 & bits != 0 {
+                    count += 1;
+                }
+                i += 1;
+            }
+            count
+        }
+
+        /// The number of set bits, including bits not covered by a flag.
+        #inline
+        pub const fn count_ones(self) -> u32 {
+            self.0.count_ones()
+        }
+
+        /// The contained single-bit flag with the highest bit, e.g. the
+        /// most significant pending interrupt.
+        #inline
+        pub const fn highest_flag(self) -> Option<#name> {
+            let mut i = Self::SORTED_FLAGS.len();
+            while i > 0 {
+                i -= 1;
+                let bits = Self::SORTED_FLAGS[i].0 as #bits_type;
+                if bits.count_ones() == 1 && self.0 & bits != 0 {
+                    return Some(Self::SORTED_FLAGS[i].0);
+                }
+            }
+            None
+        }
+
+        /// The contained single-bit flag with the lowest bit.
+        #inline
+        pub const fn lowest_flag(self) -> Option<#name> {
+            let mut i = 0;
+            while i < Self::SORTED_FLAGS.len() {
+                let bits = Self::SORTED_FLAGS[i].0 as #bits_type;
+                if bits.count_ones() == 1 && self.0 & bits != 0 {
+                    return Some(Self::SORTED_FLAGS[i].0);
+                }
+                i += 1;
+            }
+            None
+        }
+
+        /// Whether exactly one bit is set and it is the value of a listed
+        /// flag, for APIs that accept a single flag.
+        #inline
+        pub const fn is_single_flag(self) -> bool {
+            if self.0.count_ones() != 1 {
+                return false;
+            }
+            let mut i = 0;
+            while i < Self::FLAG_ORDER.len() {
+                if Self::FLAG_ORDER[i].0 as #bits_type == self.0 {
+                    return true;
+                }
+                i += 1;
+            }
+            false
+        }
+
+        /// Whether no bit is set.
+        #inline
+        pub const fn is_empty(self) -> bool {
+            self.0 == 0
+        }
+
+        /// Whether every flag in [`Self::ALL`] is set.
+        #inline
+        pub const fn is_all(self) -> bool {
+            self.0 & Self::ALL.0 == Self::ALL.0
+        }
+
+        /// The bits set in either value, `|` for `const` contexts.
+        #inline
+        pub const fn union(self, other: Self) -> Self {
+            Self(self.0 | other.0)
+        }
+
+        /// The bits set in both values, `&` for `const` contexts.
+        #inline
+        pub const fn intersection(self, other: Self) -> Self {
+            Self(self.0 & other.0)
+        }
+
+        /// The bits set in `self` but not in `other`, like `-=` for
+        /// `const` contexts.
+        #inline
+        pub const fn difference(self, other: Self) -> Self {
+            Self(self.0 & !other.0)
+        }
+
+        /// The bits set in exactly one of the values, `^` for `const`
+        /// contexts.
+        #inline
+        pub const fn symmetric_difference(self, other: Self) -> Self {
+            Self(self.0 ^ other.0)
+        }
+
+        /// The bits not covered by any flag in [`Self::ALL`], e.g. the
+        /// undeclared bits of a value read from a hardware register.
+        #inline
+        pub const fn unknown_bits(self) -> Self {
+            Self(self.0 & !Self::ALL.0)
+        }
+
+        #[doc = #truncate_doc]
+        #inline
+        pub const fn truncate(self) -> Self {
+            Self(self.0 & #valid.0)
+        }
+
+        /// Inverts the flags in [`Self::ALL`] and clears every other bit,
+        /// unlike `!`, which also sets the bits no flag covers.
+        #inline
+        pub const fn complement(self) -> Self {
+            Self(!self.0 & Self::ALL.0)
+        }
+
+        /// Returns `self` with `flag` set, for building values in `const`
+        /// initializers.
+        #inline
+        pub const fn with(self, flag: #name) -> Self {
+            Self(self.0 | flag as #bits_type)
+        }
+
+        /// Returns `self` with `flag` cleared.
+        #inline
+        pub const fn without(self, flag: #name) -> Self {
+            Self(self.0 & !(flag as #bits_type))
+        }
+
+        /// The bits gained and lost from `self` to `other`, e.g. for audit
+        /// logs of permission changes. Returns `(added, removed)`.
+        #inline
+        pub const fn diff(self, other: Self) -> (Self, Self) {
+            (Self(other.0 & !self.0), Self(self.0 & !other.0))
+        }
+
+        /// Clears every bit.
+        #inline
+        pub fn clear(&mut self) {
+            self.0 = 0;
+        }
+
+        /// Sets the bits of `other`.
+        #inline
+        pub fn insert(&mut self, other: impl Into<Self>) {
+            self.0 |= other.into().0;
+        }
+
+        /// Clears the bits of `other`.
+        #inline
+        pub fn remove(&mut self, other: impl Into<Self>) {
+            self.0 &= !other.into().0;
+        }
+
+        /// Inverts the bits of `other`.
+        #inline
+        pub fn toggle(&mut self, other: impl Into<Self>) {
+            self.0 ^= other.into().0;
+        }
+
+        /// Sets the bits of `other` if `value` is `true`, and clears them
+        /// otherwise.
+        #inline
+        pub fn set(&mut self, other: impl Into<Self>, value: bool) {
+            if value {
+                self.insert(other);
+            } else {
+                self.remove(other);
+            }
+        }
+
+        /// Replaces the value with `new`, returning the previous one.
+        #inline
+        pub fn swap(&mut self, new: impl Into<Self>) -> Self {
+            core::mem::replace(self, new.into())
+        }
+
+        /// Sets the bits of `flags`, returning whether all of them were
+        /// already set.
+        #inline
+        pub fn fetch_insert(&mut self, flags: impl Into<Self>) -> bool {
+            let flags = flags.into().0;
+            let was_set = self.0 & flags == flags;
+            self.0 |= flags;
+            was_set
+        }
+
+        /// Inverts the bits of `mask` that belong to a flag in [`Self::ALL`],
+        /// leaving every other bit as it is.
+        #inline
+        pub fn toggle_within(&mut self, mask: impl Into<Self>) {
+            self.0 ^= mask.into().0 & Self::ALL.0;
+        }
+
+        /// Iterates over the indices of the set bits in ascending order,
+        /// including bits that are not covered by a flag.
+        #inline
+        pub fn to_positions(self) -> impl Iterator<Item = u32> {
+            let mut bits = self.0;
+            core::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let position = bits.trailing_zeros();
+                bits &= bits - 1;
+                Some(position)
+            })
+        }
+
+        /// Sets the bits at the given indices, or returns `None` if an index
+        /// is out of range for the representation.
+        #inline
+        pub fn from_positions(positions: impl IntoIterator<Item = u32>) -> Option<Self> {
+            let mut bits: #bits_type = 0;
+            for position in positions {
+                bits |= (1 as #bits_type).checked_shl(position)?;
+            }
+            Some(Self(bits))
+        }
+
+        /// The listed flags and their names, ascending by value. Sorted
+        /// while evaluating the constant, as the values are only known to
+        /// the compiler.
+        #[allow(deprecated)]
+        const SORTED_FLAGS: [(#name, &'static str); #flag_count] = {
+            let mut flags: [(#name, &'static str); #flag_count] =
+                [#((#name::#variant_idents, #variant_names)),*];
+            let mut i = 1;
+            while i < flags.len() {
+                let mut j = i;
+                while j > 0 && (flags[j - 1].0 as #bits_type) > (flags[j].0 as #bits_type) {
+                    let flag = flags[j];
+                    flags[j] = flags[j - 1];
+                    flags[j - 1] = flag;
+                    j -= 1;
+                }
+                i += 1;
+            }
+            flags
+        };
+
+        /// The listed flags with the most bits first, for `decompose`.
+        /// Flags with the same number of bits stay in the order set by
+        /// `order_by`.
+        #[allow(deprecated)]
+        const WIDEST_FIRST: [#name; #flag_count] = {
+            let mut flags: [#name; #flag_count] = [#(#name::#variant_idents),*];
+            let mut i = 0;
+            while i < flags.len() {
+                flags[i] = Self::FLAG_ORDER[i].0;
+                let mut j = i;
+                while j > 0
+                    && (flags[j - 1] as #bits_type).count_ones() < (flags[j] as #bits_type).count_ones()
+                {
+                    let flag = flags[j];
+                    flags[j] = flags[j - 1];
+                    flags[j - 1] = flag;
+                    j -= 1;
+                }
+                i += 1;
+            }
+            flags
+        };
+
+        /// The listed flags and their names, in the order set by `order_by`.
+        #[allow(deprecated)]
+        const FLAG_ORDER: [(#name, &'static str); #flag_count] = #flag_order;
+
+        /// The name and value of every listed flag, including compounds, in
+        /// the order set by `order_by`, as used by `Debug` and `Display`.
+        pub const FLAGS: &'static [(&'static str, Self)] = &{
+            let mut flags: [(&'static str, Self); #flag_count] = [("", Self(0)); #flag_count];
+            let mut i = 0;
+            while i < flags.len() {
+                let (flag, flag_name) = Self::FLAG_ORDER[i];
+                flags[i] = (flag_name, Self(flag as #bits_type));
+                i += 1;
+            }
+            flags
+        };
+
+        /// Iterates over the listed flags contained in `self` ascending by
+        /// value, regardless of `order_by` and the declaration order.
+        #inline
+        pub fn sorted_flags(self) -> impl Iterator<Item = #name> {
+            Self::SORTED_FLAGS.into_iter().filter_map(move |(flag, _)| {
+                let bits = flag as #bits_type;
+                (bits != 0 && self.0 & bits == bits).then_some(flag)
+            })
+        }
+
+        /// Iterates over the names of the listed flags contained in `self`
+        /// ascending by value, for output that does not change when the enum
+        /// is reorganized.
+        #inline
+        pub fn sorted_names(self) -> impl Iterator<Item = &'static str> {
+            Self::SORTED_FLAGS.into_iter().filter_map(move |(flag, flag_name)| {
+                let bits = flag as #bits_type;
+                (bits != 0 && self.0 & bits == bits).then_some(flag_name)
+            })
+        }
+
+        /// Iterates over the listed flags contained in `self`, including
+        /// compounds, in the order set by `order_by`.
+        #inline
+        pub fn iter(self) -> #iter_name {
+            #iter_name {
+                bits: self,
+                index: 0,
+            }
+        }
+
+        /// Iterates over the names of the listed flags contained in `self`
+        /// together with their bits, in the order set by `order_by`.
+        #inline
+        pub fn iter_names(self) -> impl Iterator<Item = (&'static str, Self)> {
+            self.iter().map(|flag| (flag.name(), Self(flag as #bits_type)))
+        }
+
+        /// The bits of `self` not covered by any flag yielded by `iter()`,
+        /// which the text formats write after the flag names.
+        #inline
+        fn unnamed_bits(self) -> #bits_type {
+            self.0 & !self.iter().fold(0, |bits, flag| bits | flag as #bits_type)
+        }
+
+        /// Splits `self` into the fewest listed flags whose union is every
+        /// bit covered by a flag, which prefers compounds over the flags they
+        /// combine, and the bits not covered by any flag.
+        ///
+        /// The smallest cover is searched for starting from the cover picking
+        /// the widest flags first, which is kept among covers of the same
+        /// size. The search tries at most 1024 partial covers, each going
+        /// through the flags once, so the time a call takes grows with the
+        /// number of flags instead of exponentially with overlapping
+        /// compounds. When the budget runs out, the smallest cover found so
+        /// far is returned, which is never larger than the widest-first one.
+        #inline
+        pub fn decompose(self) -> (impl Iterator<Item = #name>, Self) {
+            let unknown = self.unnamed_bits();
+
+            // Only the flags contained in `self` can be part of the cover
+            let (cover_flags, cover_flag_count) = Self::COVER_FLAGS;
+            let mut contained = [0 as #bits_type; #flag_count];
+            let mut count = 0;
+            for bits in cover_flags.into_iter().take(cover_flag_count) {
+                if self.0 & bits == bits
+                    && let Some(slot) = contained.get_mut(count)
+                {
+                    *slot = bits;
+                    count += 1;
+                }
+            }
+            let candidates = contained.get(..count).unwrap_or_default();
+
+            // Picking the widest flag covering new bits gives a first cover,
+            // which the search only replaces with a smaller one
+            let mut best = ([0 as #bits_type; #flag_count], 0);
+            let mut uncovered = self.0 & !unknown;
+            for &bits in candidates {
+                if uncovered & bits != 0
+                    && let Some(slot) = best.0.get_mut(best.1)
+                {
+                    *slot = bits;
+                    best.1 += 1;
+                    uncovered &= !bits;
+                }
+            }
+
+            let widest = candidates
+                .first()
+                .and_then(|bits| core::num::NonZeroU32::new(bits.count_ones()))
+                .unwrap_or(core::num::NonZeroU32::MIN);
+            let mut picked = [0 as #bits_type; #flag_count];
+            let mut steps = Self::COVER_SEARCH_STEPS;
+            Self::search_cover(
+                candidates,
+                widest,
+                self.0 & !unknown,
+                &mut picked,
+                0,
+                &mut best,
+                &mut steps,
+            );
+
+            let (cover, len) = best;
+            let flags = Self::WIDEST_FIRST.into_iter().filter(move |&flag| {
+                cover.iter().take(len).any(|&bits| bits == flag as #bits_type)
+            });
+            (flags, Self(unknown))
+        }
+
+        /// The number of partial covers `decompose` tries while searching for
+        /// the smallest cover.
+        const COVER_SEARCH_STEPS: usize = 1024;
+
+        /// The values of the listed flags with bits set, widest first, and
+        /// how many there are, which `decompose` builds covers from.
+        const COVER_FLAGS: ([#bits_type; #flag_count], usize) = {
+            let mut flags = [0 as #bits_type; #flag_count];
+            let mut len = 0;
+            let mut i = 0;
+            while i < flags.len() {
+                let bits = Self::WIDEST_FIRST[i] as #bits_type;
+                if bits != 0 {
+                    flags[len] = bits;
+                    len += 1;
+                }
+                i += 1;
+            }
+            (flags, len)
+        };
+
+        /// Looks for a cover of `uncovered` by `candidates` with fewer flags
+        /// than `best`, extending the `depth` flags picked so far, while
+        /// `steps` are left. `widest` is the number of bits of the widest
+        /// candidate.
+        #inline
+        fn search_cover(
+            candidates: &[#bits_type],
+            widest: core::num::NonZeroU32,
+            uncovered: #bits_type,
+            picked: &mut [#bits_type; #flag_count],
+            depth: usize,
+            best: &mut ([#bits_type; #flag_count], usize),
+            steps: &mut usize,
+        ) {
+            if uncovered == 0 {
+                if depth < best.1 {
+                    *best = (*picked, depth);
+                }
+                return;
+            }
+            // Even flags as wide as the widest one cannot beat the best cover
+            let needed = (uncovered.count_ones() + widest.get() - 1) / widest;
+            if depth + needed as usize >= best.1 || *steps == 0 {
+                return;
+            }
+            *steps -= 1;
+
+            // Every cover has a flag with the lowest uncovered bit
+            let lowest = uncovered & uncovered.wrapping_neg();
+            for &bits in candidates {
+                if bits & lowest == 0 {
+                    continue;
+                }
+                let Some(slot) = picked.get_mut(depth) else {
+                    return;
+                };
+                *slot = bits;
+                Self::search_cover(
+                    candidates,
+                    widest,
+                    uncovered & !bits,
+                    picked,
+                    depth + 1,
+                    best,
+                    steps,
+                );
+            }
+        }
+
+        /// Calls `f` with every listed flag contained in `self`, in the order
+        /// set by `order_by`.
+        #inline
+        pub fn for_each_flag(self, f: impl FnMut(#name)) {
+            self.iter().for_each(f);
+        }
+
+        /// Panics in debug builds if bits outside of [`Self::ALL`], or of
+        /// [`Self::MASK`] with `valid_bits = "mask"`, are set, to catch
+        /// corrupted values close to where they come from. Does nothing in
+        /// release builds.
+        #[track_caller]
+        #inline
+        pub fn debug_assert_valid(&self) {
+            debug_assert!(
+                self.0 & !Self::#valid_const.0 == 0,
+                concat!("invalid ", stringify!(#bits_struct_name), ": {:#x} has bits outside of ", #valid_name),
+                self.0,
+            );
+        }
+
+        /// Returns `self`, panicking unless every bit of `other` is set.
+        /// Evaluated in a constant, this fails the build instead.
+        #[track_caller]
+        #inline
+        pub const fn assert_contains(self, other: Self) -> Self {
+            if self.0 & other.0 != other.0 {
+                panic!(concat!(stringify!(#bits_struct_name), " does not contain the expected flags"));
+            }
+            self
+        }
+
+        /// Returns `self`, panicking if it shares any bit with `other`.
+        /// Evaluated in a constant, this fails the build instead.
+        #[track_caller]
+        #inline
+        pub const fn assert_disjoint(self, other: Self) -> Self {
+            if self.0 & other.0 != 0 {
+                panic!(concat!(stringify!(#bits_struct_name), " overlaps with flags it has to exclude"));
+            }
+            self
+        }
+
+        /// Returns `self`, panicking if it has bits outside of [`Self::ALL`].
+        /// Evaluated in a constant, this fails the build instead.
+        #[track_caller]
+        #inline
+        pub const fn assert_defined(self) -> Self {
+            if self.0 & !Self::ALL.0 != 0 {
+                panic!(concat!(stringify!(#bits_struct_name), " has bits that are not covered by any flag"));
+            }
+            self
+        }
+    }
+
     impl #name {
-        #[inline]
-        pub fn bits(&self) -> #bits_type {
+        /// Every listed variant, in declaration order.
+        #[allow(deprecated)]
+        pub const VARIANTS: [Self; #flag_count] = [#(Self::#variant_idents),*];
+
+        /// The names of [`Self::VARIANTS`], in the same order.
+        pub const VARIANT_NAMES: [&'static str; #flag_count] = [#(#variant_names),*];
+
+        /// The number of listed variants, the length of [`Self::VARIANTS`].
+        pub const COUNT: usize = #flag_count;
+
+        #inline
+        pub const fn bits(&self) -> #bits_type {
             *self as #bits_type
         }
+
+        /// The index of the bit of a single-bit flag, or `None` for
+        /// compounds and other values with zero or several bits.
+        #inline
+        pub const fn bit_index(self) -> Option<u32> {
+            let bits = self as #bits_type;
+            if bits.count_ones() == 1 {
+                Some(bits.trailing_zeros())
+            } else {
+                None
+            }
+        }
+
+        /// The variant whose value is `value`, including skipped ones, or
+        /// `None` if no variant has that exact value.
+        #[allow(deprecated)]
+        #inline
+        pub const fn from_repr(value: #repr) -> Option<Self> {
+            #(
+                if value == Self::#all_variants as #repr {
+                    return Some(Self::#all_variants);
+                }
+            )*
+            None
+        }
+
+        /// The single-bit variant whose bit is at `index`, the reverse of
+        /// [`Self::bit_index`], or `None` if no variant has exactly that bit.
+        #inline
+        pub const fn try_from_bit_index(index: u32) -> Option<Self> {
+            match (1 as #repr).checked_shl(index) {
+                Some(bit) => Self::from_repr(bit),
+                None => None,
+            }
+        }
     }
 
 
     impl core::ops::BitOr for #name {
         type Output = #bits_struct_name;
-        #[inline]
-        fn bitor(self, rhs: Self) -> Self::Output {
+        #inline
+        fn bitor(self, rhs: Self) -> #bits_struct_name {
             #bits_struct_name(self.bits() | rhs.bits())
         }
     }
 
     impl core::ops::BitAnd for #name {
         type Output = #bits_struct_name;
-        #[inline]
-        fn bitand(self, rhs: Self) -> Self::Output {
+        #inline
+        fn bitand(self, rhs: Self) -> #bits_struct_name {
             #bits_struct_name(self.bits() & rhs.bits())
         }
     }
 
     impl core::ops::BitXor for #name {
         type Output = #bits_struct_name;
-        #[inline]
-        fn bitxor(self, rhs: Self) -> Self::Output {
+        #inline
+        fn bitxor(self, rhs: Self) -> #bits_struct_name {
             #bits_struct_name(self.bits() ^ rhs.bits())
         }
     }
 
     impl core::ops::Not for #name {
         type Output = #bits_struct_name;
-        #[inline]
-        fn not(self) -> Self::Output {
+        #inline
+        fn not(self) -> #bits_struct_name {
+            #bits_struct_name(!self.bits())
+        }
+    }
+
+    impl core::ops::Not for &#name {
+        type Output = #bits_struct_name;
+        #inline
+        fn not(self) -> #bits_struct_name {
             #bits_struct_name(!self.bits())
         }
     }
 
+    #(#ref_ops)*
+
+    #(#ref_assign_ops)*
+
     impl core::ops::BitOr for #bits_struct_name {
         type Output = Self;
-        #[inline]
+        #inline
         fn bitor(self, rhs: Self) -> Self {
             Self(self.0 | rhs.0)
         }
@@ -149,7 +1406,7 @@ pub fn derive(input: &ItemEnum, vis: &Visibility, name: &Ident, bits_type: &Iden
 
     impl core::ops::BitAnd for #bits_struct_name {
         type Output = Self;
-        #[inline]
+        #inline
         fn bitand(self, rhs: Self) -> Self {
             Self(self.0 & rhs.0)
         }
@@ -157,7 +1414,7 @@ pub fn derive(input: &ItemEnum, vis: &Visibility, name: &Ident, bits_type: &Iden
 
     impl core::ops::BitXor for #bits_struct_name {
         type Output = Self;
-        #[inline]
+        #inline
         fn bitxor(self, rhs: Self) -> Self {
             Self(self.0 ^ rhs.0)
         }
@@ -165,62 +1422,332 @@ pub fn derive(input: &ItemEnum, vis: &Visibility, name: &Ident, bits_type: &Iden
 
     impl core::ops::Not for #bits_struct_name {
         type Output = Self;
-        #[inline]
+        #inline
         fn not(self) -> Self {
             Self(!self.0)
         }
     }
 
     impl core::cmp::PartialEq<#name> for #bits_struct_name {
-        #[inline]
+        #inline
         fn eq(&self, other: &#name) -> bool {
             self.0 == other.bits()
         }
     }
 
     impl core::cmp::PartialEq<#bits_struct_name> for #name {
-        #[inline]
+        #inline
         fn eq(&self, other: &#bits_struct_name) -> bool {
             self.bits() == other.0
         }
     }
 
-    impl core::fmt::Debug for #bits_struct_name {
-        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-            let raw_value = self.0;
+    impl #bits_struct_name {
+        /// Writes the contained flags separated by ` | `, followed by the bits
+        /// not covered by any flag as an integer literal. With `display`, the
+        /// names are written by the `display_with` function if there is one.
+        #[allow(unused_variables)]
+        #inline
+        fn fmt_flags(&self, f: &mut core::fmt::Formatter<'_>, display: bool) -> core::fmt::Result {
             let mut first = true;
+            for flag in self.iter() {
+                if !first {
+                    f.write_str(" | ")?;
+                }
 
+                let flag_name = flag.name();
+                #display_name;
+                first = false;
+            }
+
+            // Print bits not covered by any variant, or the empty value
+            let remaining = self.unnamed_bits();
+            if remaining != 0 || first {
+                if !first {
+                    f.write_str(" | ")?;
+                }
+
+                write!(f, #unknown_format, remaining)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl core::fmt::Debug for #bits_struct_name {
+        #inline
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             f.write_str(concat!(stringify!(#bits_struct_name), "("))?;
+            self.fmt_flags(f, false)?;
+            f.write_str(")")
+        }
+    }
 
-            #(
-                {
-                    let mask_val = #name::#variant_idents as #bits_type;
+    impl core::fmt::Display for #bits_struct_name {
+        #inline
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use core::fmt::Write as _;
 
-                    if (raw_value & mask_val) == mask_val && mask_val != (0 as #bits_type) {
-                        if !first {
-                            f.write_str(" | ")?;
-                        }
+            if f.width().is_none() && f.precision().is_none() {
+                return self.fmt_flags(f, true);
+            }
 
-                        f.write_str(#variant_names)?;
+            // Counts the characters written
+            struct Measure(usize);
 
-                        first = false;
-                    }
+            impl core::fmt::Write for Measure {
+                #inline
+                fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                    self.0 += s.chars().count();
+                    Ok(())
                 }
-            )*
+            }
+
+            // Writes at most `remaining` characters
+            struct Truncate<'a, W: ?Sized> {
+                out: &'a mut W,
+                remaining: usize,
+            }
 
-            if first {
-                if raw_value == (0 as #bits_type) {
-                    f.write_str("0x0")?;
-                } else {
-                    // Print unknown bits in Hex
-                    core::fmt::LowerHex::fmt(&raw_value, f)?;
+            impl<W: core::fmt::Write + ?Sized> core::fmt::Write for Truncate<'_, W> {
+                #inline
+                fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                    let end = s.char_indices().nth(self.remaining).map_or(s.len(), |(i, _)| i);
+                    let s = s.get(..end).unwrap_or(s);
+                    self.remaining = self.remaining.saturating_sub(s.chars().count());
+                    self.out.write_str(s)
                 }
             }
 
-            f.write_str(")")
+            // The nested formatters have no width or precision, so they write
+            // the flags as is
+            let mut measure = Measure(0);
+            write!(measure, "{}", self)?;
+            let len = f.precision().map_or(measure.0, |precision| precision.min(measure.0));
+            let padding = f.width().unwrap_or(0).saturating_sub(len);
+            let (before, after) = match f.align() {
+                Some(core::fmt::Alignment::Right) => (padding, 0),
+                Some(core::fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+                _ => (0, padding),
+            };
+
+            let fill = f.fill();
+            for _ in 0..before {
+                f.write_char(fill)?;
+            }
+            write!(Truncate { out: f, remaining: len }, "{}", self)?;
+            for _ in 0..after {
+                f.write_char(fill)?;
+            }
+            Ok(())
         }
     }
+
+    #mask_impl
+    #combinations_impl
+    #bits_type_checks
+    #validate_checks
+
+    #extends_callback
+
+    #extends_impl
+
+    #subset_impl
+
+    #default_impl
+
+    #enum_debug_impl
+
+    #alloc_impl
+    #std_impl
+
+    #compact_impl
+    #ffi_impl
+    #stable_abi_impl
+
+    #names_impl
+
+    #map_impl
+
+    #fields_impl
+    #groups_impl
+
+    #tracked_impl
+
+    #usage_impl
+
+    #interop_impl
     };
 
-    TokenStream::from(expanded)
+    expanded
+}
+
+/// Replaces every variant depending on a `cfg_attr` compound with one variant
+/// per value, each gated by the `cfg` it applies under.
+fn with_cfg_variants(input: &ItemEnum, variant_options: &[VariantOptions]) -> ItemEnum {
+    let mut item = input.clone();
+    item.variants = input
+        .variants
+        .iter()
+        .zip(variant_options)
+        .flat_map(|(variant, options)| {
+            if options.cfg_values.is_empty() {
+                return vec![variant.clone()];
+            }
+            options
+                .cfg_values
+                .iter()
+                .map(|value| {
+                    let mut variant = variant.clone();
+                    let (cfg, expr) = (&value.cfg, &value.expr);
+                    variant
+                        .attrs
+                        .push(syn::parse_quote!(#[cfg(all(#(#cfg),*))]));
+                    variant.discriminant = Some((Default::default(), expr.clone()));
+                    variant
+                })
+                .collect()
+        })
+        .collect();
+    item
+}
+
+/// Traits the generated code relies on the enum implementing.
+const ENUM_DERIVES: [&str; 8] = [
+    "Clone",
+    "Copy",
+    "Debug",
+    "PartialEq",
+    "Eq",
+    "PartialOrd",
+    "Ord",
+    "Hash",
+];
+
+/// Adds the derives the enum needs, leaving out the ones the user already
+/// derives, and `Debug` when it is implemented by hand. The derive goes after
+/// every other attribute, so attribute macros below `#[bitmask]` still expand
+/// before it.
+fn with_derives(input: &ItemEnum, manual_debug: bool) -> ItemEnum {
+    let mut derived = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("derive")) {
+        // A malformed derive is reported by the compiler when it expands.
+        let _ = attr.parse_nested_meta(|meta| {
+            if let Some(last) = meta.path.segments.last() {
+                derived.push(last.ident.to_string());
+            }
+            Ok(())
+        });
+    }
+
+    let missing: Vec<_> = ENUM_DERIVES
+        .iter()
+        .filter(|d| !derived.iter().any(|found| found == *d))
+        .filter(|d| !(manual_debug && **d == "Debug"))
+        .map(|d| Ident::new(d, Span::call_site()))
+        .collect();
+
+    let mut item = input.clone();
+    if !missing.is_empty() {
+        item.attrs.push(syn::parse_quote!(#[derive(#(#missing),*)]));
+    }
+    item
+}
+
+/// Documents the bits struct with a table of every listed flag.
+fn struct_docs(
+    name: &Ident,
+    repr: &Ident,
+    variants: &[(&Variant, &VariantOptions)],
+) -> Vec<String> {
+    let width = repr_width(repr);
+    let mut docs = vec![
+        format!(" A set of [`{name}`] flags."),
+        String::new(),
+        " | Flag | Value | Compound |".to_string(),
+        " |------|-------|----------|".to_string(),
+    ];
+
+    for (variant, options) in variants.iter().filter(|(_, o)| !o.skip) {
+        let value = match &variant.discriminant {
+            Some((_, expr)) => match eval(expr, width) {
+                Some(value) => format!("`{value:#x}`"),
+                None => format!("`{}`", escape_cell(&expr.to_token_stream().to_string())),
+            },
+            None => "depends on `cfg`".to_string(),
+        };
+        let mut compounds: Vec<String> = options
+            .cfg_compound_sources()
+            .into_iter()
+            .map(|(predicate, source)| {
+                format!(
+                    "`{}` if `{}`",
+                    escape_cell(&source),
+                    escape_cell(&predicate)
+                )
+            })
+            .collect();
+        compounds.extend(
+            options
+                .compound_source()
+                .map(|source| format!("`{}`", escape_cell(&source))),
+        );
+        let compound = match compounds.len() {
+            0 | 1 => compounds.concat(),
+            _ => compounds.join(", otherwise "),
+        };
+        let deprecated = if options.deprecated {
+            " (deprecated)"
+        } else {
+            ""
+        };
+
+        docs.push(format!(
+            " | `{}`{deprecated} | {value} | {compound} |",
+            variant.ident
+        ));
+    }
+
+    docs
+}
+
+fn escape_cell(source: &str) -> String {
+    source.replace('|', "\\|")
+}
+
+/// The `#[doc(alias = "...")]` names given to listed variants, so searching
+/// rustdoc for them also finds the bits struct.
+fn doc_aliases(variants: &[(&Variant, &VariantOptions)]) -> Vec<LitStr> {
+    let mut aliases = Vec::new();
+    for (variant, _) in variants.iter().filter(|(_, o)| !o.skip) {
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("doc")) {
+            if !matches!(attr.meta, Meta::List(_)) {
+                continue;
+            }
+            // Other `doc` properties are not ours to validate, so anything
+            // that fails to parse is left to rustdoc.
+            let _ = attr.parse_nested_meta(|meta| {
+                let alias = meta.path.is_ident("alias");
+                if meta.input.peek(Token![=]) {
+                    let value = meta.value()?;
+                    if alias {
+                        aliases.push(value.parse()?);
+                    } else {
+                        value.parse::<Expr>()?;
+                    }
+                } else if meta.input.peek(token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    if alias {
+                        aliases
+                            .extend(Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?);
+                    } else {
+                        content.parse::<TokenStream>()?;
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+    aliases
 }
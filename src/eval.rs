@@ -0,0 +1,59 @@
+use syn::{BinOp, Expr, Ident, Lit, UnOp};
+
+/// Width in bits of a supported representation, `None` for `usize` whose
+/// width depends on the target.
+pub fn repr_width(repr: &Ident) -> Option<u32> {
+    match repr.to_string().as_str() {
+        "u8" => Some(8),
+        "u16" => Some(16),
+        "u32" => Some(32),
+        "u64" => Some(64),
+        "u128" => Some(128),
+        _ => None,
+    }
+}
+
+/// Evaluates a resolved discriminant expression at macro time.
+///
+/// Only literals and operators are understood. Anything else, like references
+/// to constants, yields `None` and has to be left to the compiler.
+pub fn eval(expr: &Expr, width: Option<u32>) -> Option<u128> {
+    let value = match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => int.base10_parse::<u128>().ok()?,
+            _ => return None,
+        },
+        Expr::Paren(paren) => eval(&paren.expr, width)?,
+        Expr::Group(group) => eval(&group.expr, width)?,
+        Expr::Cast(cast) => eval(&cast.expr, width)?,
+        Expr::Unary(unary) => match unary.op {
+            UnOp::Not(_) => !eval(&unary.expr, width)? & width_mask(width?),
+            _ => return None,
+        },
+        Expr::Binary(binary) => {
+            let left = eval(&binary.left, width)?;
+            let right = eval(&binary.right, width)?;
+            match binary.op {
+                BinOp::BitOr(_) => left | right,
+                BinOp::BitAnd(_) => left & right,
+                BinOp::BitXor(_) => left ^ right,
+                BinOp::Shl(_) => left.checked_shl(u32::try_from(right).ok()?)?,
+                BinOp::Shr(_) => left.checked_shr(u32::try_from(right).ok()?)?,
+                BinOp::Add(_) => left.checked_add(right)?,
+                BinOp::Sub(_) => left.checked_sub(right)?,
+                BinOp::Mul(_) => left.checked_mul(right)?,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    match width {
+        Some(width) if value & !width_mask(width) != 0 => None,
+        _ => Some(value),
+    }
+}
+
+fn width_mask(width: u32) -> u128 {
+    u128::MAX >> (128 - width)
+}
@@ -0,0 +1,178 @@
+//! Support for `#[bitmask(extends = Base)]`.
+//!
+//! A proc macro cannot look at another item's definition, so every bitmask
+//! emits a hidden `macro_rules!` callback that knows its variants. An extending
+//! enum is forwarded through the base's callback, which re-invokes `#[bitmask]`
+//! with the inherited variants listed in `__inherit(...)`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Ident, Path, Variant, Visibility};
+
+use crate::VariantOptions;
+
+/// The variants received from a base bitmask through `__inherit(...)`.
+pub struct Inherited {
+    pub repr: Ident,
+    pub variants: Vec<Variant>,
+}
+
+fn callback_ident(name: &Ident) -> Ident {
+    format_ident!("__bitmask_{}", name)
+}
+
+/// The path of the bits type generated for the bitmask at `path`.
+pub fn bits_path(path: &Path) -> Path {
+    let mut bits = path.clone();
+    if let Some(last) = bits.segments.last_mut() {
+        last.ident = format_ident!("{}Bits", last.ident);
+    }
+    bits
+}
+
+/// Hands the extending enum to the callback of its base.
+pub fn forward(base: &Path, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut callback = base.clone();
+    if let Some(last) = callback.segments.last_mut() {
+        last.ident = callback_ident(&last.ident);
+    }
+
+    quote! {
+        #callback! { [#attr] #item }
+    }
+}
+
+/// The callback through which other bitmasks can extend this one.
+///
+/// The callback of a private enum is imported privately, so it stays usable
+/// from child modules without widening the visibility of items that are local
+/// to a function.
+pub fn callback(
+    vis: &Visibility,
+    name: &Ident,
+    bits_type: &Ident,
+    variants: &[(&Variant, &VariantOptions)],
+) -> TokenStream {
+    let callback = callback_ident(name);
+    let import_vis = match vis {
+        Visibility::Inherited => quote!(),
+        _ => quote!(pub(crate)),
+    };
+    let inherited = variants.iter().map(|(v, o)| {
+        let ident = &v.ident;
+        let attrs = v.attrs.iter().filter(|a| forwarded(a));
+        let skip = o.skip.then(|| quote!(#[bitmask(skip)]));
+        quote!(#(#attrs)* #skip #ident)
+    });
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #callback {
+            ([$($attr:tt)*] $($item:tt)*) => {
+                #[::enum_bitmasks::bitmask(__inherit(#bits_type, #(#inherited),*), $($attr)*)]
+                $($item)*
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #import_vis use #callback;
+    }
+}
+
+/// Attributes of a base variant that carry over to the inherited variant.
+fn forwarded(attr: &Attribute) -> bool {
+    attr.path().is_ident("doc") || attr.path().is_ident("deprecated")
+}
+
+/// Turns the variants received from `base` into variants of the extending
+/// enum carrying the same values.
+pub fn inherit(base: &Path, bits_type: &Ident, inherited: &Inherited) -> Vec<Variant> {
+    inherited
+        .variants
+        .iter()
+        .map(|v| {
+            let mut variant = v.clone();
+            let ident = &variant.ident;
+            variant.discriminant = Some((
+                Default::default(),
+                syn::parse_quote!(#base::#ident as #bits_type),
+            ));
+            if variant
+                .attrs
+                .iter()
+                .any(|a| a.path().is_ident("deprecated"))
+            {
+                variant.attrs.push(syn::parse_quote!(#[allow(deprecated)]));
+            }
+            variant
+        })
+        .collect()
+}
+
+/// Names the constant holding the first bit after the inherited ones, so
+/// auto-assigned variants of the extending enum do not collide with them.
+pub fn first_free_bit(name: &Ident) -> Ident {
+    format_ident!("__{}_FIRST_FREE_BIT", name.to_string().to_uppercase())
+}
+
+/// Conversions between the base and the extending types.
+pub fn conversions(
+    base: &Path,
+    inherited: &Inherited,
+    name: &Ident,
+    bits_struct_name: &Ident,
+    error_name: &Ident,
+    bits_type: &Ident,
+    inline: &TokenStream,
+) -> TokenStream {
+    let base_repr = &inherited.repr;
+    let idents: Vec<_> = inherited.variants.iter().map(|v| &v.ident).collect();
+    let base_bits = bits_path(base);
+
+    let first_free_bit = first_free_bit(name);
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(deprecated)]
+        const #first_free_bit: u32 =
+            #bits_type::BITS - (0 #(| (#base::#idents as #bits_type))*).leading_zeros();
+
+        const _: () = assert!(
+            core::mem::size_of::<#base>() <= core::mem::size_of::<#name>(),
+            concat!(stringify!(#name), " must have a representation at least as wide as its base"),
+        );
+
+        impl core::convert::From<#base> for #name {
+            #inline
+            #[allow(deprecated)]
+            fn from(val: #base) -> Self {
+                match val {
+                    #(#base::#idents => Self::#idents,)*
+                }
+            }
+        }
+
+        impl core::convert::From<#base_bits> for #bits_struct_name {
+            #inline
+            fn from(val: #base_bits) -> Self {
+                Self(#base_repr::from(val) as #bits_type)
+            }
+        }
+
+        impl core::convert::TryFrom<#bits_struct_name> for #base_bits {
+            type Error = #error_name;
+
+            #inline
+            fn try_from(val: #bits_struct_name) -> Result<Self, Self::Error> {
+                let undefined = val.0 & !(#base_repr::from(#base_bits::ALL) as #bits_type);
+                if undefined == 0 {
+                    Ok(#base_bits::from_bits_retain(val.0 as #base_repr))
+                } else {
+                    Err(#error_name(undefined))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::derive::Context;
+
+/// Conversions to the C integer types flags are passed as.
+///
+/// `c_uint` and `c_ulong` are aliases of `u32` or `u64` depending on the
+/// platform, so `From` and `TryFrom` impls for them would conflict with the
+/// ones for the representation. Checked methods are generated instead.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    let conversions = ["c_uint", "c_ulong"].map(|c_type| {
+        let to = format_ident!("to_{}", c_type);
+        let from = format_ident!("from_{}", c_type);
+        let c_type = format_ident!("{}", c_type);
+        let to_doc = format!(
+            " Converts to a `{c_type}`, or returns `None` if the bits do not fit into it on this platform."
+        );
+        let from_doc = format!(
+            " Converts from a `{c_type}`, or returns `None` if it has bits that do not fit into the representation or are not covered by a flag."
+        );
+
+        quote! {
+            #[doc = #to_doc]
+            #inline
+            pub fn #to(self) -> Option<core::ffi::#c_type> {
+                core::ffi::#c_type::try_from(self.0).ok()
+            }
+
+            #[doc = #from_doc]
+            #inline
+            pub fn #from(bits: core::ffi::#c_type) -> Option<Self> {
+                let bits: #bits_type = bits.try_into().ok()?;
+                Self::try_from(bits).ok()
+            }
+        }
+    });
+
+    quote! {
+        impl #bits_struct_name {
+            #(#conversions)*
+        }
+    }
+}
+
+/// Layout checks and `extern "C"` functions for `#[bitmask(stable_abi)]`, so
+/// the bits type can be part of FFI-stable interfaces like plugin vtables.
+/// The functions are associated rather than `#[no_mangle]`, as exported
+/// symbols of every bitmask would collide.
+pub fn stable_abi(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    quote! {
+        const _: () = {
+            assert!(
+                core::mem::size_of::<#bits_struct_name>() == core::mem::size_of::<#bits_type>(),
+                concat!(stringify!(#bits_struct_name), " must have the size of its representation"),
+            );
+            assert!(
+                core::mem::align_of::<#bits_struct_name>() == core::mem::align_of::<#bits_type>(),
+                concat!(stringify!(#bits_struct_name), " must have the alignment of its representation"),
+            );
+        };
+
+        impl #bits_struct_name {
+            /// `from_bits_retain` with the C calling convention.
+            #inline
+            pub extern "C" fn ffi_from_bits_retain(bits: #bits_type) -> Self {
+                Self(bits)
+            }
+
+            /// The raw bits, with the C calling convention.
+            #inline
+            pub extern "C" fn ffi_bits(self) -> #bits_type {
+                self.0
+            }
+
+            /// Whether every bit of `other` is set, with the C calling
+            /// convention.
+            #inline
+            pub extern "C" fn ffi_contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Ident, Variant};
+
+use crate::{VariantOptions, derive::Context, names::snake_case};
+
+/// A struct with one `bool` per flag, for code that prefers named fields over
+/// bit operations.
+pub fn impls(cx: &Context, variants: &[(&Variant, &VariantOptions)]) -> TokenStream {
+    let Context {
+        vis,
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    let struct_name = format_ident!("{}Struct", name);
+    // Compounds are made of other flags, so only single flags get a field
+    let flags: Vec<_> = variants
+        .iter()
+        .filter(|(_, o)| !o.skip && o.compound.is_none() && o.cfg_compounds.is_empty())
+        .map(|(v, _)| v)
+        .collect();
+    let idents: Vec<_> = flags.iter().map(|v| &v.ident).collect();
+    let fields: Vec<_> = idents.iter().map(|i| field_ident(i)).collect();
+    let docs = flags.iter().map(|v| {
+        let docs = v.attrs.iter().filter(|a| a.path().is_ident("doc"));
+        quote!(#(#docs)*)
+    });
+
+    quote! {
+        #[doc = concat!(" [`", stringify!(#bits_struct_name), "`] as one `bool` per flag.")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        #vis struct #struct_name {
+            #(#docs pub #fields: bool,)*
+        }
+
+        impl #bits_struct_name {
+            /// Splits the bits into one `bool` per flag. Bits not belonging
+            /// to any non-compound flag are dropped.
+            #[allow(deprecated)]
+            #inline
+            pub const fn to_struct(self) -> #struct_name {
+                #struct_name {
+                    #(#fields: {
+                        let flag = #name::#idents as #bits_type;
+                        flag != 0 && self.0 & flag == flag
+                    },)*
+                }
+            }
+
+            /// Combines the flags set in `fields`.
+            #[allow(deprecated)]
+            #inline
+            pub const fn from_struct(fields: #struct_name) -> Self {
+                let mut bits: #bits_type = 0;
+                #(if fields.#fields {
+                    bits |= #name::#idents as #bits_type;
+                })*
+                Self(bits)
+            }
+        }
+
+        impl core::convert::From<#bits_struct_name> for #struct_name {
+            #inline
+            fn from(bits: #bits_struct_name) -> Self {
+                bits.to_struct()
+            }
+        }
+
+        impl core::convert::From<#struct_name> for #bits_struct_name {
+            #inline
+            fn from(fields: #struct_name) -> Self {
+                Self::from_struct(fields)
+            }
+        }
+    }
+}
+
+/// The field named after `variant`, escaping keywords.
+fn field_ident(variant: &Ident) -> Ident {
+    let field = snake_case(&variant.to_string());
+    match field.as_str() {
+        // These cannot be raw identifiers
+        "crate" | "self" | "super" => format_ident!("{}_", field),
+        _ if syn::parse_str::<Ident>(&field).is_err() => Ident::new_raw(&field, Span::call_site()),
+        _ => Ident::new(&field, variant.span()),
+    }
+}
@@ -0,0 +1,88 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Ident, Variant};
+
+use crate::{VariantOptions, derive::Context};
+
+/// Constants, predicates and iteration for the variants tagged with
+/// `#[bitmask(group = "...")]`.
+pub fn impls(cx: &Context, variants: &[(&Variant, &VariantOptions)]) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    // Groups in the order they are first used, with their members in
+    // declaration order. Skipped variants are hidden from their groups like
+    // from `ALL` and `iter()`, but still declare them
+    let mut groups: Vec<(String, Vec<&Ident>)> = Vec::new();
+    for (variant, options) in variants {
+        for group in &options.groups {
+            let group = group.value();
+            let i = match groups.iter().position(|(g, _)| *g == group) {
+                Some(i) => i,
+                None => {
+                    groups.push((group, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            if !options.skip {
+                groups[i].1.push(&variant.ident);
+            }
+        }
+    }
+
+    let items = groups.iter().map(|(group, members)| {
+        let constant = format_ident!("GROUP_{}", group.to_uppercase());
+        let predicate = format_ident!("has_{}_flags", group);
+        let iter = format_ident!("{}_flags", group);
+        let toggle = format_ident!("toggle_{}_flags", group);
+        let constant_doc = format!(" The flags of the `{group}` group.");
+        let predicate_doc = format!(" Whether any flag of the `{group}` group is set.");
+        let toggle_doc =
+            format!(" Inverts the flags of the `{group}` group, leaving every other bit as it is.");
+        let iter_doc = format!(
+            " Iterates over the contained flags of the `{group}` group, in declaration order."
+        );
+
+        quote! {
+            #[doc = #constant_doc]
+            #[allow(deprecated)]
+            pub const #constant: Self = Self(0 #(| #name::#members as #bits_type)*);
+
+            #[doc = #predicate_doc]
+            #inline
+            pub const fn #predicate(self) -> bool {
+                self.0 & Self::#constant.0 != 0
+            }
+
+            #[doc = #toggle_doc]
+            #inline
+            pub fn #toggle(&mut self) {
+                self.toggle_within(Self::#constant);
+            }
+
+            #[doc = #iter_doc]
+            #[allow(deprecated)]
+            #inline
+            pub fn #iter(self) -> impl Iterator<Item = #name> {
+                [#(#name::#members),*].into_iter().filter(move |&flag| {
+                    let bits = flag as #bits_type;
+                    bits != 0 && self.0 & bits == bits
+                })
+            }
+        }
+    });
+
+    if groups.is_empty() {
+        return TokenStream::new();
+    }
+    quote! {
+        impl #bits_struct_name {
+            #(#items)*
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Integrations with third-party crates, each behind the crate feature of
+//! the same name. The generated code names the third-party crate directly, so
+//! the crate using `#[bitmask]` has to depend on it as well.
+
+use proc_macro2::TokenStream;
+#[allow(unused_imports)]
+use quote::quote;
+
+use crate::derive::Context;
+
+#[cfg(feature = "enumflags2")]
+mod enumflags2;
+#[cfg(feature = "hash32")]
+mod hash32;
+#[cfg(feature = "inventory")]
+mod inventory;
+#[cfg(feature = "modular-bitfield")]
+mod modular_bitfield;
+#[cfg(feature = "prost")]
+mod prost;
+#[cfg(feature = "pyo3")]
+mod pyo3;
+#[cfg(feature = "rand")]
+mod rand;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "strum")]
+mod strum;
+#[cfg(feature = "subtle")]
+mod subtle;
+#[cfg(feature = "ufmt")]
+mod ufmt;
+#[cfg(feature = "uniffi")]
+mod uniffi;
+#[cfg(feature = "valuable")]
+mod valuable;
+
+#[allow(unused_mut, unused_variables)]
+pub fn impls(cx: &Context) -> TokenStream {
+    let mut impls = TokenStream::new();
+
+    #[cfg(feature = "valuable")]
+    impls.extend(valuable::impls(cx));
+    #[cfg(feature = "prost")]
+    impls.extend(prost::impls(cx));
+    #[cfg(feature = "pyo3")]
+    impls.extend(pyo3::impls(cx));
+    #[cfg(feature = "uniffi")]
+    impls.extend(uniffi::impls(cx));
+    #[cfg(feature = "redis")]
+    impls.extend(redis::impls(cx));
+    #[cfg(feature = "ufmt")]
+    impls.extend(ufmt::impls(cx));
+    #[cfg(feature = "strum")]
+    impls.extend(strum::impls(cx));
+    #[cfg(feature = "rand")]
+    impls.extend(rand::impls(cx));
+    #[cfg(feature = "inventory")]
+    impls.extend(inventory::impls(cx));
+    #[cfg(feature = "serde")]
+    impls.extend(serde::impls(cx));
+    #[cfg(feature = "enumflags2")]
+    impls.extend(enumflags2::impls(cx));
+    #[cfg(feature = "hash32")]
+    impls.extend(hash32::impls(cx));
+    #[cfg(feature = "subtle")]
+    impls.extend(subtle::impls(cx));
+    #[cfg(feature = "modular-bitfield")]
+    impls.extend(modular_bitfield::impls(cx));
+
+    impls
+}
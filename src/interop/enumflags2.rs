@@ -0,0 +1,76 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// enumflags2's `BitFlag` for the enum, for `#[bitmask(enumflags2)]`, and
+/// conversions between `BitFlags` and the bits type.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        variants,
+        options,
+        ..
+    } = cx;
+    if !options.enumflags2 {
+        return TokenStream::new();
+    }
+    let default = if options.default_values.is_empty() {
+        quote!(0)
+    } else {
+        quote!(#bits_struct_name::DEFAULT.0)
+    };
+    let messages = variants.iter().map(|ident| {
+        format!("enumflags2 requires every variant to be a single bit, `{ident}` is not")
+    });
+
+    quote! {
+        // `BitFlags` turns single bits back into variants, so anything else
+        // would be undefined behavior
+        #[allow(deprecated)]
+        const _: () = {
+            #(assert!((#name::#variants as #bits_type).count_ones() == 1, #messages);)*
+        };
+
+        // SAFETY: every variant is a single bit, and `ALL_BITS` is their union.
+        // `RawBitFlags` is private to enumflags2 and matches its 0.7.12 release
+        unsafe impl ::enumflags2::_internal::RawBitFlags for #name {
+            type Numeric = #bits_type;
+
+            const EMPTY: #bits_type = 0;
+
+            const DEFAULT: #bits_type = #default;
+
+            #[allow(deprecated)]
+            const ALL_BITS: #bits_type = 0 #(| (#name::#variants as #bits_type))*;
+
+            const BITFLAGS_TYPE_NAME: &'static str = concat!("BitFlags<", stringify!(#name), ">");
+
+            #inline
+            fn bits(self) -> #bits_type {
+                self as #bits_type
+            }
+        }
+
+        impl ::enumflags2::BitFlag for #name {}
+
+        impl core::convert::From<::enumflags2::BitFlags<#name>> for #bits_struct_name {
+            #inline
+            fn from(flags: ::enumflags2::BitFlags<#name>) -> Self {
+                Self(flags.bits())
+            }
+        }
+
+        impl core::convert::TryFrom<#bits_struct_name> for ::enumflags2::BitFlags<#name> {
+            type Error = ::enumflags2::FromBitsError<#name>;
+
+            #inline
+            fn try_from(bits: #bits_struct_name) -> Result<Self, Self::Error> {
+                Self::from_bits(bits.0)
+            }
+        }
+    }
+}
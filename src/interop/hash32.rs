@@ -0,0 +1,41 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// `hash32::Hash` for the enum and the bits type, hashing the representation
+/// so both can be keys of heapless 0.7 maps. Newer heapless versions take
+/// `core::hash::Hash`, which is always derived.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    quote! {
+        impl ::hash32::Hash for #bits_struct_name {
+            #inline
+            fn hash<H>(&self, state: &mut H)
+            where
+                H: ::hash32::Hasher,
+            {
+                // hash32 has no impl for 128 bit integers, so write the bytes
+                state.write(&self.0.to_ne_bytes())
+            }
+        }
+
+        impl ::hash32::Hash for #name {
+            #[allow(deprecated)]
+            #inline
+            fn hash<H>(&self, state: &mut H)
+            where
+                H: ::hash32::Hasher,
+            {
+                state.write(&(*self as #bits_type).to_ne_bytes())
+            }
+        }
+    }
+}
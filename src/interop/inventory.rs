@@ -0,0 +1,31 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// Submits the flag table of the bitmask to the `inventory` registry of the
+/// type given by `#[bitmask(register = Type)]`.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_type,
+        listed,
+        register,
+        ..
+    } = cx;
+    let Some(register) = register else {
+        return TokenStream::new();
+    };
+    let names: Vec<String> = listed.iter().map(|i| i.to_string()).collect();
+
+    quote! {
+        ::inventory::submit! {
+            #[allow(deprecated)]
+            #register::new(
+                stringify!(#name),
+                #bits_type::BITS,
+                &[#((#names, #name::#listed as u128)),*],
+            )
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// `modular_bitfield::Specifier` for the bits type, so it can be a field of a
+/// `#[bitfield]` struct. The field is as wide as needed for the highest bit
+/// of any variant.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        repr,
+        variants,
+        inline,
+        ..
+    } = cx;
+
+    // The representation holds every bit of the field, so it can be the base
+    // type modular-bitfield computes with, except for `usize`, which it does
+    // not support and `u64` holds on every target
+    let bytes = if *repr == "usize" {
+        quote!(u64)
+    } else {
+        quote!(#repr)
+    };
+
+    quote! {
+        #[allow(deprecated)]
+        const _: () = {
+            const USED: #bits_type = 0 #(| #name::#variants as #bits_type)*;
+            const WIDTH: usize = (#bits_type::BITS - USED.leading_zeros()) as usize;
+
+            impl ::modular_bitfield::Specifier for #bits_struct_name {
+                const BITS: usize = WIDTH;
+                type Bytes = #bytes;
+                type InOut = Self;
+
+                #inline
+                fn into_bytes(
+                    input: Self,
+                ) -> Result<#bytes, ::modular_bitfield::error::OutOfBounds> {
+                    if input.0.checked_shr(WIDTH as u32).unwrap_or(0) != 0 {
+                        return Err(::modular_bitfield::error::OutOfBounds);
+                    }
+                    Ok(input.0 as #bytes)
+                }
+
+                #inline
+                fn from_bytes(
+                    bytes: #bytes,
+                ) -> Result<Self, ::modular_bitfield::error::InvalidBitPattern<#bytes>> {
+                    Ok(Self(bytes as #bits_type))
+                }
+            }
+        };
+    }
+}
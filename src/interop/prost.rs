@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{derive::Context, eval::repr_width};
+
+/// Conversions to and from the `int32` prost uses for enums and the `uint32`
+/// used for bitfield-style fields.
+///
+/// Only the lossy decode is generated for representations wider than 32 bits,
+/// since their values do not generally fit into a protobuf 32-bit field.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        repr,
+        inline,
+        variants,
+        options,
+        ..
+    } = cx;
+
+    let lossy = quote! {
+        impl #bits_struct_name {
+            /// Decodes a protobuf `uint32` field, returning the contained flags
+            /// and the bits that are not covered by any flag.
+            #inline
+            pub const fn from_proto_lossy(value: u32) -> (Self, u32) {
+                let all = Self::ALL.0 as u128;
+                let value = value as u128;
+                (Self((value & all) as #bits_type), (value & !all) as u32)
+            }
+        }
+    };
+
+    if repr_width(repr).is_none_or(|width| width > 32) {
+        return lossy;
+    }
+
+    // With `bits_type` the wrapped integer is not known, and may be `u32`
+    let bits_into_u32 = (options.bits_type.is_none() && *repr != "u32").then(|| {
+        quote! {
+            impl core::convert::From<#bits_struct_name> for u32 {
+                #inline
+                fn from(val: #bits_struct_name) -> Self {
+                    val.0 as u32
+                }
+            }
+        }
+    });
+
+    quote! {
+        #lossy
+
+        #bits_into_u32
+
+        impl core::convert::From<#name> for i32 {
+            #inline
+            fn from(val: #name) -> Self {
+                val as #bits_type as u32 as i32
+            }
+        }
+
+        impl core::convert::TryFrom<i32> for #name {
+            type Error = ::prost::UnknownEnumValue;
+
+            #[allow(deprecated)]
+            #inline
+            fn try_from(value: i32) -> Result<Self, ::prost::UnknownEnumValue> {
+                #(
+                    if value as u32 == #name::#variants as #bits_type as u32 {
+                        return Ok(#name::#variants);
+                    }
+                )*
+                Err(::prost::UnknownEnumValue(value))
+            }
+        }
+    }
+}
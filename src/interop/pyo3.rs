@@ -0,0 +1,59 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// Conversions between the bits type and Python `int`, plus a constructor for
+/// an `enum.IntFlag` class carrying the named flags.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        listed,
+        ..
+    } = cx;
+
+    quote! {
+        impl<'py> ::pyo3::conversion::IntoPyObject<'py> for #bits_struct_name {
+            type Target = <#bits_type as ::pyo3::conversion::IntoPyObject<'py>>::Target;
+            type Output = <#bits_type as ::pyo3::conversion::IntoPyObject<'py>>::Output;
+            type Error = <#bits_type as ::pyo3::conversion::IntoPyObject<'py>>::Error;
+
+            #inline
+            fn into_pyobject(self, py: ::pyo3::Python<'py>) -> Result<Self::Output, Self::Error> {
+                ::pyo3::conversion::IntoPyObject::into_pyobject(self.0, py)
+            }
+        }
+
+        impl<'a, 'py> ::pyo3::conversion::FromPyObject<'a, 'py> for #bits_struct_name {
+            type Error = ::pyo3::PyErr;
+
+            #inline
+            fn extract(obj: ::pyo3::Borrowed<'a, 'py, ::pyo3::PyAny>) -> Result<Self, Self::Error> {
+                let bits = <#bits_type as ::pyo3::conversion::FromPyObject>::extract(obj)
+                    .map_err(::core::convert::Into::<::pyo3::PyErr>::into)?;
+                Self::try_from(bits)
+                    .map_err(|e| ::pyo3::exceptions::PyValueError::new_err(e.to_string()))
+            }
+        }
+
+        impl #bits_struct_name {
+            /// Creates a Python `enum.IntFlag` subclass named after the enum,
+            /// with a member for every flag.
+            ///
+            /// Add it to a module to expose the flags to Python code.
+            #[allow(deprecated)]
+            #inline
+            pub fn py_flag_class(py: ::pyo3::Python<'_>) -> ::pyo3::PyResult<::pyo3::Bound<'_, ::pyo3::PyAny>> {
+                use ::pyo3::types::PyAnyMethods as _;
+
+                let members = [#((stringify!(#listed), #name::#listed as #bits_type)),*];
+                py.import("enum")?
+                    .getattr("IntFlag")?
+                    .call1((stringify!(#name), members))
+            }
+        }
+    }
+}
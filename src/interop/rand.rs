@@ -0,0 +1,40 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{derive::Context, eval::repr_width};
+
+/// Random combinations of the defined flags, for simulations and tests.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        repr,
+        inline,
+        ..
+    } = cx;
+
+    // Draws as few random words as the representation needs, `usize` being
+    // at most 64 bits wide
+    let random = match repr_width(repr) {
+        Some(width) if width <= 32 => quote!(rng.next_u32() as #bits_type),
+        Some(128) => {
+            quote!(((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) as #bits_type)
+        }
+        _ => quote!(rng.next_u64() as #bits_type),
+    };
+
+    quote! {
+        impl #bits_struct_name {
+            /// Returns a uniformly random combination of the bits in
+            /// [`Self::ALL`], so every value without bits outside of the flags
+            /// is equally likely.
+            ///
+            /// Unlike a random integer, the result never has bits that are not
+            /// covered by a flag.
+            #inline
+            pub fn sample_defined<R: ::rand::Rng + ?Sized>(rng: &mut R) -> Self {
+                Self(#random & Self::ALL.0)
+            }
+        }
+    }
+}
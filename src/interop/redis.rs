@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// `ToRedisArgs` and `FromRedisValue` for the bits type, stored as the raw
+/// integer. Values with undefined bits fail to parse.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    quote! {
+        impl ::redis::ToRedisArgs for #bits_struct_name {
+            #inline
+            fn write_redis_args<W>(&self, out: &mut W)
+            where
+                W: ?Sized + ::redis::RedisWrite,
+            {
+                ::redis::ToRedisArgs::write_redis_args(&self.0, out)
+            }
+
+            #inline
+            fn describe_numeric_behavior(&self) -> ::redis::NumericBehavior {
+                ::redis::ToRedisArgs::describe_numeric_behavior(&self.0)
+            }
+        }
+
+        impl ::redis::FromRedisValue for #bits_struct_name {
+            #inline
+            fn from_redis_value_ref(v: &::redis::Value) -> Result<Self, ::redis::ParsingError> {
+                let bits = <#bits_type as ::redis::FromRedisValue>::from_redis_value_ref(v)?;
+                Self::try_from(bits).map_err(|e| ::redis::ParsingError::from(e.to_string()))
+            }
+
+            #inline
+            fn from_redis_value(v: ::redis::Value) -> Result<Self, ::redis::ParsingError> {
+                Self::from_redis_value_ref(&v)
+            }
+        }
+    }
+}
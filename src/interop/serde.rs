@@ -0,0 +1,119 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{Radix, derive::Context};
+
+/// `Serialize` and `Deserialize` for the bits type: a list of flag names for
+/// human-readable formats, and the raw integer for binary ones.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        unknown_radix,
+        inline,
+        ..
+    } = cx;
+    let unknown_format = match unknown_radix {
+        Radix::Bin => "{:#b}",
+        Radix::Hex => "{:#x}",
+        Radix::Dec => "{}",
+    };
+
+    quote! {
+        impl ::serde::Serialize for #bits_struct_name {
+            #inline
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                use ::serde::ser::SerializeSeq as _;
+
+                if !serializer.is_human_readable() {
+                    return ::serde::Serialize::serialize(&self.0, serializer);
+                }
+
+                // Bits not covered by a flag, written as an integer literal
+                struct Unknown(#bits_type);
+
+                impl ::serde::Serialize for Unknown {
+                    #inline
+                    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: ::serde::Serializer,
+                    {
+                        serializer.collect_str(&format_args!(#unknown_format, self.0))
+                    }
+                }
+
+                let remaining = self.unnamed_bits();
+                let len = self.iter().count() + usize::from(remaining != 0);
+                let mut seq = serializer.serialize_seq(Some(len))?;
+                for (flag_name, _) in self.iter_names() {
+                    seq.serialize_element(flag_name)?;
+                }
+                if remaining != 0 {
+                    seq.serialize_element(&Unknown(remaining))?;
+                }
+                seq.end()
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for #bits_struct_name {
+            #inline
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                if !deserializer.is_human_readable() {
+                    return <#bits_type as ::serde::Deserialize>::deserialize(deserializer).map(Self);
+                }
+
+                // Accepts a list of names or literals, the text written by
+                // `Display`, or a plain integer
+                struct Visitor;
+
+                impl<'de> ::serde::de::Visitor<'de> for Visitor {
+                    type Value = #bits_struct_name;
+
+                    #inline
+                    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        f.write_str(concat!("a list of ", stringify!(#bits_struct_name), " flag names"))
+                    }
+
+                    #inline
+                    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+                    where
+                        E: ::serde::de::Error,
+                    {
+                        s.parse()
+                            .map_err(|_| E::invalid_value(::serde::de::Unexpected::Str(s), &self))
+                    }
+
+                    #inline
+                    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                    where
+                        E: ::serde::de::Error,
+                    {
+                        #bits_type::try_from(v)
+                            .map(#bits_struct_name)
+                            .map_err(|_| E::invalid_value(::serde::de::Unexpected::Unsigned(v), &self))
+                    }
+
+                    #inline
+                    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: ::serde::de::SeqAccess<'de>,
+                    {
+                        let mut bits = #bits_struct_name(0);
+                        while let Some(part) = seq.next_element::<#bits_struct_name>()? {
+                            bits |= part;
+                        }
+                        Ok(bits)
+                    }
+                }
+
+                deserializer.deserialize_any(Visitor)
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// The traits `strum` derives for the name of a variant: `IntoStaticStr`,
+/// `EnumString` and `VariantNames`, and for the list of variants: `EnumIter`
+/// and `EnumCount`.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        variants,
+        listed,
+        inline,
+        ..
+    } = cx;
+    let flag_count = listed.len();
+
+    quote! {
+        impl core::convert::From<#name> for &'static str {
+            #[allow(deprecated)]
+            #inline
+            fn from(val: #name) -> Self {
+                match val {
+                    #(#name::#variants => stringify!(#variants),)*
+                }
+            }
+        }
+
+        impl core::convert::From<&#name> for &'static str {
+            #inline
+            fn from(val: &#name) -> Self {
+                (*val).into()
+            }
+        }
+
+        impl core::str::FromStr for #name {
+            type Err = ::strum::ParseError;
+
+            #inline
+            fn from_str(s: &str) -> Result<Self, ::strum::ParseError> {
+                Self::try_from(s).map_err(|_| ::strum::ParseError::VariantNotFound)
+            }
+        }
+
+        impl ::strum::VariantNames for #name {
+            const VARIANTS: &'static [&'static str] = &[#(stringify!(#listed)),*];
+        }
+
+        impl ::strum::EnumCount for #name {
+            const COUNT: usize = #flag_count;
+        }
+
+        impl ::strum::IntoEnumIterator for #name {
+            type Iterator = core::array::IntoIter<#name, #flag_count>;
+
+            #inline
+            fn iter() -> core::array::IntoIter<#name, #flag_count> {
+                #name::VARIANTS.into_iter()
+            }
+        }
+    }
+}
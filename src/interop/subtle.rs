@@ -0,0 +1,50 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// Constant-time comparisons and selection of the bits type, for masks checked
+/// in authentication and authorization paths.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        inline,
+        ..
+    } = cx;
+
+    quote! {
+        impl ::subtle::ConstantTimeEq for #bits_struct_name {
+            #inline
+            fn ct_eq(&self, other: &Self) -> ::subtle::Choice {
+                ::subtle::ConstantTimeEq::ct_eq(&self.0, &other.0)
+            }
+        }
+
+        impl ::subtle::ConditionallySelectable for #bits_struct_name {
+            #inline
+            fn conditional_select(a: &Self, b: &Self, choice: ::subtle::Choice) -> Self {
+                // All ones when `b` is chosen, masking like subtle does for the
+                // integers it implements this for, which leave out `usize`
+                let mask = (choice.unwrap_u8() as #bits_type).wrapping_neg();
+                Self(a.0 ^ (mask & (a.0 ^ b.0)))
+            }
+        }
+
+        impl #bits_struct_name {
+            /// Whether every bit of `other` is set, without branching on
+            /// the bits.
+            #inline
+            pub fn ct_contains(self, other: Self) -> ::subtle::Choice {
+                ::subtle::ConstantTimeEq::ct_eq(&(self.0 & other.0), &other.0)
+            }
+
+            /// Whether any bit of `other` is set, without branching on the
+            /// bits.
+            #inline
+            pub fn ct_intersects(self, other: Self) -> ::subtle::Choice {
+                !::subtle::ConstantTimeEq::ct_eq(&(self.0 & other.0), &0)
+            }
+        }
+    }
+}
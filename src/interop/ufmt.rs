@@ -0,0 +1,88 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+
+use crate::{Radix, derive::Context};
+
+/// `uDebug` and `uDisplay` for the bits type, writing the decomposed flag
+/// names without going through `core::fmt`.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        bits_struct_name,
+        bits_type,
+        unknown_radix,
+        inline,
+        ..
+    } = cx;
+    let (radix, prefix) = match unknown_radix {
+        Radix::Bin => (2u32, "0b"),
+        Radix::Hex => (16, "0x"),
+        Radix::Dec => (10, ""),
+    };
+    let radix = Literal::u32_unsuffixed(radix);
+
+    quote! {
+        impl #bits_struct_name {
+            /// Writes `Read | Write`, followed by any bits not covered by a
+            /// flag.
+            #inline
+            fn __ufmt_flags<W>(&self, f: &mut ::ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+            where
+                W: ::ufmt::uWrite + ?Sized,
+            {
+                let mut first = true;
+                for (flag_name, _) in self.iter_names() {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+                    f.write_str(flag_name)?;
+                    first = false;
+                }
+
+                let mut remaining = self.unnamed_bits();
+                if remaining != 0 || first {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+
+                    let mut digits = [0u8; #bits_type::BITS as usize];
+                    let mut start = digits.len();
+                    loop {
+                        start -= 1;
+                        digits[start] = b"0123456789abcdef"[(remaining % #radix) as usize];
+                        remaining /= #radix;
+                        if remaining == 0 {
+                            break;
+                        }
+                    }
+
+                    f.write_str(#prefix)?;
+                    f.write_str(core::str::from_utf8(&digits[start..]).unwrap_or_default())?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl ::ufmt::uDebug for #bits_struct_name {
+            #inline
+            fn fmt<W>(&self, f: &mut ::ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+            where
+                W: ::ufmt::uWrite + ?Sized,
+            {
+                f.write_str(concat!(stringify!(#bits_struct_name), "("))?;
+                self.__ufmt_flags(f)?;
+                f.write_str(")")
+            }
+        }
+
+        impl ::ufmt::uDisplay for #bits_struct_name {
+            #inline
+            fn fmt<W>(&self, f: &mut ::ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+            where
+                W: ::ufmt::uWrite + ?Sized,
+            {
+                self.__ufmt_flags(f)
+            }
+        }
+    }
+}
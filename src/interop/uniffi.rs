@@ -0,0 +1,49 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{derive::Context, names::snake_case};
+
+/// Registers the bits type as a UniFFI 0.28 custom type over its
+/// representation, and exports a function returning the named flags so
+/// foreign callers share the flag catalog.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        vis,
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        listed,
+        ..
+    } = cx;
+
+    let flags_fn = format_ident!("{}_flags", snake_case(&name.to_string()));
+
+    quote! {
+        ::uniffi::custom_type!(#bits_struct_name, #bits_type);
+
+        impl crate::UniffiCustomTypeConverter for #bits_struct_name {
+            type Builtin = #bits_type;
+
+            #inline
+            fn into_custom(bits: #bits_type) -> ::uniffi::Result<Self> {
+                Ok(Self::try_from(bits)?)
+            }
+
+            #inline
+            fn from_custom(bits: Self) -> #bits_type {
+                bits.0
+            }
+        }
+
+        /// The flags of the bitmask by name.
+        #[::uniffi::export]
+        #[allow(deprecated)]
+        #inline
+        #vis fn #flags_fn() -> ::std::collections::HashMap<::std::string::String, #bits_struct_name> {
+            ::std::collections::HashMap::from([
+                #((stringify!(#listed).to_string(), #bits_struct_name::from(#name::#listed))),*
+            ])
+        }
+    }
+}
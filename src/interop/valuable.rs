@@ -0,0 +1,56 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::derive::Context;
+
+/// Records the bits as the list of contained flag names, and the enum as its
+/// variant name.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        variants,
+        inline,
+        ..
+    } = cx;
+    let variant_names: Vec<String> = variants.iter().map(|i| i.to_string()).collect();
+
+    quote! {
+        impl ::valuable::Valuable for #name {
+            #inline
+            fn as_value(&self) -> ::valuable::Value<'_> {
+                #[allow(deprecated)]
+                ::valuable::Value::String(match self {
+                    #(#name::#variants => #variant_names,)*
+                })
+            }
+
+            #inline
+            fn visit(&self, visit: &mut dyn ::valuable::Visit) {
+                visit.visit_value(self.as_value());
+            }
+        }
+
+        impl ::valuable::Valuable for #bits_struct_name {
+            #inline
+            fn as_value(&self) -> ::valuable::Value<'_> {
+                ::valuable::Value::Listable(self)
+            }
+
+            #inline
+            fn visit(&self, visit: &mut dyn ::valuable::Visit) {
+                for (flag_name, _) in self.iter_names() {
+                    visit.visit_value(::valuable::Value::String(flag_name));
+                }
+            }
+        }
+
+        impl ::valuable::Listable for #bits_struct_name {
+            #inline
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.iter().count();
+                (len, Some(len))
+            }
+        }
+    }
+}
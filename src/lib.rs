@@ -2,27 +2,59 @@ use std::collections::HashMap;
 
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use syn::{Attribute, Error, Expr, Ident, ItemEnum, Variant, parse_macro_input};
+use quote::ToTokens;
+use syn::{
+    Attribute, Error, Expr, Ident, ItemEnum, LitStr, Meta, Path, Token, Variant, parse::Parser,
+    parse_macro_input, punctuated::Punctuated, token,
+};
 
-use crate::{derive::derive, resolve_masks::resolve_variant};
+use crate::{
+    derive::derive,
+    extends::Inherited,
+    resolve_masks::{Compound, Fallback, Resolved, resolve_expr, resolve_variant},
+    sidecar::{Sidecar, write_sidecar},
+};
 
+mod compact;
 mod derive;
+mod eval;
+mod extends;
+mod ffi;
+mod fields;
+mod groups;
+mod interop;
+mod map;
+mod match_flags;
+mod names;
 mod resolve_masks;
+mod sidecar;
+mod subset;
+#[cfg(test)]
+mod tests;
+mod tracked;
+mod usage;
 
 /// ## `#[bitmask]`
 ///
 /// Declares a *bitmask definition* using an `enum` and
 /// generates a corresponding transparent *bits type* for runtime use.
-
+///
+/// This macro is intentionally **low-level**. It does **not** attempt to model
+/// permissions, states, or invariants. It only provides:
+///
 /// - Explicit bit values
 /// - Compile-time composition of bits
 /// - A thin, transparent wrapper around the raw integer
 ///
+/// The README describes every option, generated method and crate feature in
+/// detail.
+///
 /// ### Overview
 ///
 /// Given an enum definition:
 ///
 /// ```rust
+/// # use enum_bitmasks::bitmask;
 /// #[bitmask(enable_auto_assign)]
 /// #[repr(u8)]
 /// pub enum Permissions {
@@ -48,13 +80,18 @@ mod resolve_masks;
 ///
 /// A concrete integer representation is **required**:
 ///
-/// ```rust
+/// ```rust,ignore
 /// #[repr(u8 | u16 | u32 | u64 | u128 | usize)]
 /// ```
 ///
 /// Signed integer representations are **not supported**.
 /// Bitmasks are defined in terms of unsigned bitwise operations only.
 ///
+/// Other attributes of the enum are kept as they are. The enum derives `Clone`,
+/// `Copy`, `Debug`, `PartialEq`, `Eq`, `PartialOrd`, `Ord` and `Hash` unless it
+/// already does, so derives of these traits have to be written below
+/// `#[bitmask]`, where the macro can see them.
+///
 ///
 /// ### Variant Assignment Rules
 ///
@@ -62,13 +99,13 @@ mod resolve_masks;
 ///
 /// - Have an explicit discriminant:
 ///
-///   ```rust
+///   ```rust,ignore
 ///   A = 0b0001
 ///   ```
 ///
 /// - Use `#[compound(...)]` to combine previously defined variants:
 ///
-///   ```rust
+///   ```rust,ignore
 ///   #[compound(A | B)]
 ///   C
 ///   ```
@@ -90,10 +127,11 @@ mod resolve_masks;
 /// - Parentheses
 /// - Unary operators (e.g. `!`)
 /// - Integer literals
+/// - Variants of the enum, referenced as `A`, `Self::A` or `Permissions::A`
 ///
 /// Example:
 ///
-/// ```rust
+/// ```rust,ignore
 /// #[compound(A | (B | C))]
 /// D
 /// ```
@@ -104,14 +142,15 @@ mod resolve_masks;
 /// - Checked for infinite recursion
 /// - Expanded into concrete discriminant values
 ///
-/// Cyclic definitions are rejected with a compile-time error.
+/// Cyclic definitions are rejected with a compile-time error. A compound can
+/// also be chosen per configuration with `#[cfg_attr(..., compound(...))]`.
 ///
 /// ### `enable_auto_assign`
 ///
 /// When enabled, variants without explicit values or `#[compound]` are assigned
 /// sequential single-bit values:
 ///
-/// ```rust
+/// ```rust,ignore
 /// A = 1 << 0
 /// B = 1 << 1
 /// C = 1 << 2
@@ -122,11 +161,26 @@ mod resolve_masks;
 /// - Ordering matters
 /// - Auto-assignment cannot be mixed with explicit discriminants
 ///
+/// ### Other Options
+///
+/// - `#[bitmask(skip)]` on a variant leaves it out of `ALL` and the flag listings
+/// - `#[bitmask(group = "...")]` on a variant adds it to a named group of flags
+/// - `bits_type`, `bits_attrs`: the integer and extra attributes of the bits type
+/// - `mask`, `valid_bits`: the region of the integer that holds valid bits
+/// - `default`: the value of `Default` for the bits type
+/// - `include_deprecated`: keeps `#[deprecated]` variants in `ALL` and parsing
+/// - `inline`, `order_by`, `unknown_radix`, `enum_debug`, `display_with`: code
+///   generation and formatting
+/// - `tracked`, `report_usage`, `all_combinations`, `stable_abi`, `sidecar`:
+///   additional generated items
+/// - `extends`, `subset_of`: bitmasks derived from another bitmask
+/// - `validate`: a `const fn` every variant value has to pass
+///
 /// ### Generated Types
 ///
 /// For an enum named `Permissions`, this macro generates:
 ///
-/// ```rust
+/// ```rust,ignore
 /// pub struct PermissionsBits(repr_type);
 /// ```
 ///
@@ -136,9 +190,13 @@ mod resolve_masks;
 /// - Copyable
 /// - Comparable
 /// - Hashable
+/// - `#[must_use]`
 ///
 /// The bits type is a **thin wrapper** around the raw integer.
 ///
+/// It also generates `PermissionsMap<T>`, holding a value per variant, and
+/// `PermissionsStruct`, with a `bool` field per flag.
+///
 /// ### Operators
 ///
 /// The following operators are implemented:
@@ -162,7 +220,8 @@ mod resolve_masks;
 ///
 /// - `Permissions → PermissionsBits`
 /// - `PermissionsBits → repr_type`
-/// - `repr_type → PermissionsBits`
+/// - `repr_type → PermissionsBits` (`TryFrom`, rejecting bits not covered by a
+///   flag; `from_bits_retain` keeps them, `from_bits_truncate` clears them)
 /// - `Permissions → repr_type`
 ///
 /// ### Debug Formatting
@@ -180,139 +239,771 @@ mod resolve_masks;
 ///
 /// - `0` is printed as `0x0`
 /// - Unknown bits are printed in hexadecimal
+///
+/// `Display` writes the same text without the type name, and `FromStr` parses
+/// it back.
+///
+/// ### Crate features
+///
+/// - `alloc`, `std`: conversions to and from `Vec` and the standard sets
+/// - `enumflags2`, `hash32`, `inventory`, `modular-bitfield`, `prost`, `pyo3`,
+///   `rand`, `redis`, `serde`, `strum`, `subtle`, `ufmt`, `uniffi`,
+///   `valuable`: implementations of the traits of the crate of the same name,
+///   which the crate using `#[bitmask]` has to depend on as well
 #[proc_macro_attribute]
 pub fn bitmask(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut input = parse_macro_input!(item as ItemEnum);
+    expand(attr.into(), item.into()).into()
+}
+
+/// Expands `#[bitmask]`, working on `proc_macro2` tokens so the expansion can
+/// be inspected by the tests.
+fn expand(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let attr_tokens = attr.clone();
+    let item_tokens = item.clone();
+    let mut input = match syn::parse2::<ItemEnum>(item) {
+        Ok(input) => input,
+        Err(e) => return e.into_compile_error(),
+    };
     let mut all_errors: Option<Error> = None;
     let name = &input.ident;
     let vis = &input.vis;
 
     let repr = check_repr(&input.attrs);
     if let Err(e) = repr {
-        return e.into_compile_error().into();
+        return e.into_compile_error();
     }
     let repr = repr.unwrap();
 
-    let mut enable_auto = false;
+    let mut options = BitmaskOptions::default();
 
     let parser = syn::meta::parser(|meta| {
         if meta.path.is_ident("enable_auto_assign") {
-            enable_auto = true;
+            options.enable_auto = true;
+            Ok(())
+        } else if meta.path.is_ident("mask") {
+            options.mask = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("inline") {
+            let value: LitStr = meta.value()?.parse()?;
+            options.inline = match value.value().as_str() {
+                "always" => Inline::Always,
+                "hint" => Inline::Hint,
+                "never" => Inline::Never,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected one of \"always\", \"hint\" or \"never\"",
+                    ));
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("unknown_radix") {
+            let value: LitStr = meta.value()?.parse()?;
+            options.unknown_radix = match value.value().as_str() {
+                "bin" => Radix::Bin,
+                "hex" => Radix::Hex,
+                "dec" => Radix::Dec,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected one of \"bin\", \"hex\" or \"dec\"",
+                    ));
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("enum_debug") {
+            let value: LitStr = meta.value()?.parse()?;
+            options.enum_debug = match value.value().as_str() {
+                "name" => EnumDebug::Name,
+                "verbose" => EnumDebug::Verbose,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected one of \"name\" or \"verbose\"",
+                    ));
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("valid_bits") {
+            let value: LitStr = meta.value()?.parse()?;
+            options.valid_bits = match value.value().as_str() {
+                "all" => ValidBits::All,
+                "mask" => ValidBits::Mask,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected one of \"all\" or \"mask\"",
+                    ));
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("order_by") {
+            let value: LitStr = meta.value()?.parse()?;
+            options.order_by = match value.value().as_str() {
+                "decl" => Order::Decl,
+                "bit" => Order::Bit,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "expected one of \"decl\" or \"bit\"",
+                    ));
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("sidecar") {
+            options.sidecar = Some(if meta.input.peek(Token![=]) {
+                Sidecar::Path(meta.value()?.parse()?)
+            } else {
+                Sidecar::OutDir
+            });
+            Ok(())
+        } else if meta.path.is_ident("extends") {
+            options.extends = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("subset_of") {
+            options.subset_of = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("__inherit") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            let repr = content.parse()?;
+            content.parse::<Token![,]>()?;
+            let variants = Punctuated::<Variant, Token![,]>::parse_terminated(&content)?;
+            options.inherited = Some(Inherited {
+                repr,
+                variants: variants.into_iter().collect(),
+            });
+            Ok(())
+        } else if meta.path.is_ident("include_deprecated") {
+            options.include_deprecated = true;
+            Ok(())
+        } else if meta.path.is_ident("bits_attrs") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            while !content.is_empty() {
+                options
+                    .bits_attrs
+                    .extend(content.call(Attribute::parse_outer)?);
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+            Ok(())
+        } else if meta.path.is_ident("enumflags2") {
+            if !cfg!(feature = "enumflags2") {
+                return Err(
+                    meta.error("`enumflags2` requires the `enumflags2` feature of enum-bitmasks")
+                );
+            }
+            options.enumflags2 = true;
+            Ok(())
+        } else if meta.path.is_ident("register") {
+            if !cfg!(feature = "inventory") {
+                return Err(
+                    meta.error("`register` requires the `inventory` feature of enum-bitmasks")
+                );
+            }
+            options.register = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("report_usage") {
+            options.report_usage = true;
+            Ok(())
+        } else if meta.path.is_ident("stable_abi") {
+            options.stable_abi = true;
+            Ok(())
+        } else if meta.path.is_ident("validate") {
+            options.validate = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("all_combinations") {
+            options.all_combinations = true;
+            Ok(())
+        } else if meta.path.is_ident("tracked") {
+            options.tracked = true;
+            Ok(())
+        } else if meta.path.is_ident("display_with") {
+            options.display_with = Some(meta.value()?.parse()?);
             Ok(())
         } else if meta.path.is_ident("default") {
+            // A bare `default` was accepted and ignored before it took a value
+            if meta.input.peek(Token![=]) {
+                options.default = Some(meta.value()?.parse()?);
+            }
+            Ok(())
+        } else if meta.path.is_ident("bits_type") {
+            options.bits_type = Some(meta.value()?.parse()?);
             Ok(())
         } else {
             Err(meta.error("unsupported bitmasks property"))
         }
     });
 
-    parse_macro_input!(attr with parser);
+    if let Err(e) = parser.parse2(attr) {
+        return e.into_compile_error();
+    }
+    let enable_auto = options.enable_auto;
+
+    if matches!(options.valid_bits, ValidBits::Mask) && options.mask.is_none() {
+        return syn::Error::new(
+            Span::call_site(),
+            "`valid_bits = \"mask\"` requires a `mask`",
+        )
+        .into_compile_error();
+    }
+
+    if let Some(parent) = &options.subset_of
+        && (enable_auto || options.extends.is_some())
+    {
+        return syn::Error::new_spanned(
+            parent,
+            "Conflict: subset_of cannot be combined with enable_auto_assign or extends",
+        )
+        .into_compile_error();
+    }
+
+    if let Some(bits_type) = &options.bits_type
+        && (options.extends.is_some() || options.subset_of.is_some())
+    {
+        return syn::Error::new_spanned(
+            bits_type,
+            "Conflict: bits_type cannot be combined with extends or subset_of",
+        )
+        .into_compile_error();
+    }
 
     let mut variants: Vec<Variant> = input.variants.into_iter().collect();
-    let mut resolved_values = HashMap::<Ident, Expr>::new();
+    let mut inherited_count = 0;
+    let mut first_free_bit = None;
+    if let Some(base) = &options.extends {
+        let Some(inherited) = &options.inherited else {
+            return extends::forward(base, attr_tokens, item_tokens);
+        };
+        let inherited_variants = extends::inherit(base, &repr, inherited);
+        inherited_count = inherited_variants.len();
+        variants.splice(0..0, inherited_variants);
+        first_free_bit = Some(extends::first_free_bit(name));
+    }
+    let mut resolved_values = HashMap::<Ident, Vec<Resolved>>::new();
 
-    let mut compound_idxs: Vec<(usize, Attribute)> = Vec::new();
-    let mut shift = 0;
+    let mut compounds: Vec<Option<Compound>> = Vec::new();
+    let mut variant_options: Vec<VariantOptions> = Vec::new();
+    let mut shift = 0u32;
     for (i, variant) in variants.iter_mut().enumerate() {
+        if let Err(e) = check_helper_attrs(variant) {
+            return e.into_compile_error();
+        }
+
+        match take_variant_options(variant) {
+            Ok(options) => variant_options.push(options),
+            Err(e) => return e.into_compile_error(),
+        }
+
         let comp_idx = variant
             .attrs
             .iter()
             .position(|a| a.path().is_ident("compound"));
+        let cfg_compounds = std::mem::take(&mut variant_options[i].cfg_compounds);
+        let conditional = !cfg_compounds.is_empty();
+        let mut fallback = None;
 
         if let Some((_, expr)) = &variant.discriminant {
-            if enable_auto {
+            if enable_auto && i >= inherited_count {
                 let e = syn::Error::new_spanned(
                     &variant.ident,
                     "Conflict: Remove enable_auto_assign to manually assign values",
                 );
-                return e.into_compile_error().into();
-            } else if let Some(_) = comp_idx {
+                return e.into_compile_error();
+            } else if comp_idx.is_some() {
                 let e = syn::Error::new_spanned(
                     &variant.ident,
                     "Conflict: Variant has both a explicit value and a #[compound] attribute.",
                 );
-                return e.into_compile_error().into();
+                return e.into_compile_error();
+            } else if let Some(parent) = &options.subset_of {
+                let e = syn::Error::new_spanned(
+                    &variant.ident,
+                    format!(
+                        "Conflict: Variants of a subset take their value from {}",
+                        parent.to_token_stream()
+                    ),
+                );
+                return e.into_compile_error();
             }
-            resolved_values.insert(variant.ident.clone(), expr.clone());
-        } else {
-            if let None = comp_idx {
-                if enable_auto {
-                    let expr: Expr = syn::parse_quote!(1 << #shift);
-                    shift += 1;
-                    resolved_values.insert(variant.ident.clone(), expr.clone());
-                    variant.discriminant = Some((Default::default(), expr.clone()));
+            if conditional {
+                // The explicit value applies when no `cfg_attr` compound does
+                fallback = Some(Fallback::Value(expr.clone()));
+                variant.discriminant = None;
+            } else {
+                resolved_values.insert(variant.ident.clone(), vec![Resolved::new(expr.clone())]);
+            }
+        } else if comp_idx.is_none() {
+            if let Some(parent) = &options.subset_of {
+                let expr = subset::value(parent, variant, &repr);
+                if conditional {
+                    fallback = Some(Fallback::Value(expr));
                 } else {
-                    let e = syn::Error::new_spanned(
-                        &variant.ident,
-                        "Variant should have either an explicit value or a #[compound(...)] attribute.",
-                    );
-                    return e.into_compile_error().into();
+                    resolved_values
+                        .insert(variant.ident.clone(), vec![Resolved::new(expr.clone())]);
+                    variant.discriminant = Some((Default::default(), expr));
                 }
+            } else if conditional {
+                // Without a fallback, configurations where no `cfg_attr`
+                // compound applies fail to compile.
+            } else if enable_auto {
+                let expr: Expr = match &first_free_bit {
+                    Some(first_free_bit) => syn::parse_quote!(1 << (#first_free_bit + #shift)),
+                    None => syn::parse_quote!(1 << #shift),
+                };
+                shift += 1;
+                resolved_values.insert(variant.ident.clone(), vec![Resolved::new(expr.clone())]);
+                variant.discriminant = Some((Default::default(), expr.clone()));
+            } else {
+                let e = syn::Error::new_spanned(
+                    &variant.ident,
+                    "Variant should have either an explicit value or a #[compound(...)] attribute.",
+                );
+                return e.into_compile_error();
             }
         }
 
         if let Some(idx) = comp_idx {
-            compound_idxs.push((i, variant.attrs.remove(idx)));
+            let attr = variant.attrs.remove(idx);
+            variant_options[i].compound = Some(attr.clone());
+            fallback = Some(Fallback::Compound(attr));
         }
+
+        compounds.push((comp_idx.is_some() || conditional).then(|| Compound {
+            branches: cfg_compounds.clone(),
+            fallback,
+        }));
+        variant_options[i].cfg_compounds = cfg_compounds;
     }
 
-    for (i, attr) in compound_idxs {
+    for i in 0..variants.len() {
+        if compounds[i].is_none() {
+            continue;
+        }
         let mut computed_idents: Vec<Ident> = Vec::new();
-        let resolve_variant = resolve_variant(
+        let resolved = resolve_variant(
             i,
-            attr,
-            &mut variants,
+            &compounds,
+            name,
+            &variants,
             &mut resolved_values,
             &mut computed_idents,
         );
-        if let Err(e) = resolve_variant {
-            match &mut all_errors {
+        match resolved {
+            Ok(mut resolved) => {
+                if let [single] = resolved.as_slice()
+                    && single.cfg.is_empty()
+                {
+                    variants[i].discriminant = Some((Default::default(), single.expr.clone()));
+                } else {
+                    // Emitted as one `#[cfg]`-gated variant per value
+                    variant_options[i].cfg_values = std::mem::take(&mut resolved);
+                }
+            }
+            Err(e) => match &mut all_errors {
                 Some(existing_error) => existing_error.combine(e),
                 None => all_errors = Some(e),
-            }
+            },
+        }
+    }
+
+    if let Some(default) = &options.default {
+        let resolved = resolve_expr(
+            default.clone(),
+            default,
+            &compounds,
+            name,
+            &variants,
+            &mut resolved_values,
+            &mut Vec::new(),
+        );
+        match resolved {
+            Ok(resolved) => options.default_values = resolved,
+            Err(e) => match &mut all_errors {
+                Some(existing_error) => existing_error.combine(e),
+                None => all_errors = Some(e),
+            },
         }
     }
 
     if let Some(e) = all_errors {
-        return e.to_compile_error().into();
+        return e.to_compile_error();
     }
 
     input.variants = variants.into_iter().collect();
 
-    TokenStream::from(derive(&input, vis, name, &repr))
+    if let Some(target) = &options.sidecar
+        && let Err(e) = write_sidecar(target, &input, &repr, &variant_options)
+    {
+        return e.into_compile_error();
+    }
+
+    derive(&input, vis, name, &repr, &variant_options, &options)
 }
 
-fn check_repr(attrs: &[Attribute]) -> Result<Ident, syn::Error> {
-    attrs
-        .iter()
-        .filter(|a| a.path().is_ident("repr"))
-        .find_map(|a| {
-            let mut repr_type = None;
-            let res = a
-                .parse_nested_meta(|meta| {
-                    if meta.path.is_ident("C") {
-                        return Ok(());
-                    }
-                    if meta.path.is_ident("u8")
-                        || meta.path.is_ident("u16")
-                        || meta.path.is_ident("u32")
-                        || meta.path.is_ident("u64")
-                        || meta.path.is_ident("u128")
-                        || meta.path.is_ident("usize")
+/// ## `match_flags!`
+///
+/// Runs an arm for every flag contained in a bits value, with the arms checked
+/// for exhaustiveness like a `match` on the enum. Adding a variant makes every
+/// `match_flags!` without an arm for it fail to compile.
+///
+/// ```rust
+/// # use enum_bitmasks::{bitmask, match_flags};
+/// #[bitmask]
+/// #[repr(u8)]
+/// pub enum Permissions {
+///     Read = 0b01,
+///     Write = 0b10,
+/// }
+///
+/// let mut granted = Vec::new();
+/// match_flags!(Permissions::Read | Permissions::Write,
+///     Permissions::Read => granted.push("read"),
+///     Permissions::Write => granted.push("write"),
+/// );
+/// assert_eq!(granted, ["read", "write"]);
+/// ```
+///
+/// The arms run in decomposition order (see `order_by`), through
+/// `PermissionsBits::for_each_flag`, for every listed variant whose bits are
+/// all set, compounds included. Their bodies are closures, so `return`,
+/// `break` and `?` do not reach the surrounding function.
+#[proc_macro]
+pub fn match_flags(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as match_flags::MatchFlags);
+    TokenStream::from(match_flags::expand(&input))
+}
+
+#[derive(Default)]
+struct BitmaskOptions {
+    enable_auto: bool,
+    mask: Option<Expr>,
+    include_deprecated: bool,
+    inline: Inline,
+    unknown_radix: Radix,
+    order_by: Order,
+    enum_debug: EnumDebug,
+    valid_bits: ValidBits,
+    sidecar: Option<Sidecar>,
+    extends: Option<Path>,
+    inherited: Option<Inherited>,
+    subset_of: Option<Path>,
+    default: Option<Expr>,
+    display_with: Option<Path>,
+    tracked: bool,
+    report_usage: bool,
+    /// Checks the layout of the bits type and adds `extern "C"` functions.
+    stable_abi: bool,
+    /// A `const fn(repr) -> bool` every variant value has to pass.
+    validate: Option<Path>,
+    /// Generates `all_combinations()`, which is opt-in as it yields `2^N`
+    /// values.
+    all_combinations: bool,
+    /// Implements enumflags2's `BitFlag` for the enum.
+    enumflags2: bool,
+    /// The `inventory` registry the flag table is submitted to.
+    register: Option<Path>,
+    /// Attributes put on the bits type as they are.
+    bits_attrs: Vec<Attribute>,
+    /// The integer type wrapped by the bits type, when it differs from the
+    /// `repr`, e.g. a type alias.
+    bits_type: Option<Path>,
+    /// The values of `default`, one per `cfg` when it depends on a
+    /// `cfg_attr` compound.
+    default_values: Vec<Resolved>,
+}
+
+/// The inlining attribute put on generated methods and operator impls.
+#[derive(Default, Clone, Copy)]
+enum Inline {
+    Always,
+    #[default]
+    Hint,
+    Never,
+}
+
+/// How bits that are not covered by a flag are formatted.
+#[derive(Default, Clone, Copy)]
+enum Radix {
+    Bin,
+    #[default]
+    Hex,
+    Dec,
+}
+
+/// How `Debug` formats a variant of the enum.
+#[derive(Default, Clone, Copy)]
+enum EnumDebug {
+    #[default]
+    Name,
+    Verbose,
+}
+
+/// The bits `debug_assert_valid` accepts.
+#[derive(Default, Clone, Copy)]
+enum ValidBits {
+    #[default]
+    All,
+    Mask,
+}
+
+/// The order in which contained flags are listed.
+#[derive(Default, Clone, Copy)]
+enum Order {
+    #[default]
+    Decl,
+    Bit,
+}
+
+#[derive(Default)]
+struct VariantOptions {
+    skip: bool,
+    deprecated: bool,
+    compound: Option<Attribute>,
+    /// The `#[bitmask(group = "...")]` names, in order.
+    groups: Vec<LitStr>,
+    /// `#[cfg_attr(predicate, compound(...))]` attributes, in order.
+    cfg_compounds: Vec<(proc_macro2::TokenStream, Attribute)>,
+    /// The values of a variant that depends on a `cfg_attr` compound, emitted
+    /// as one `#[cfg]`-gated variant each. Empty for all other variants.
+    cfg_values: Vec<Resolved>,
+}
+
+impl VariantOptions {
+    /// The expression inside `#[compound(...)]`, as written.
+    fn compound_source(&self) -> Option<String> {
+        let list = self.compound.as_ref()?.meta.require_list().ok()?;
+        Some(list.tokens.to_string())
+    }
+
+    /// The predicate and expression of every `cfg_attr` compound, as written.
+    fn cfg_compound_sources(&self) -> Vec<(String, String)> {
+        self.cfg_compounds
+            .iter()
+            .filter_map(|(predicate, attr)| {
+                let list = attr.meta.require_list().ok()?;
+                Some((predicate.to_string(), list.tokens.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Strips the `#[bitmask(...)]` helper attributes from a variant and collects
+/// the properties they set.
+fn take_variant_options(variant: &mut Variant) -> Result<VariantOptions, syn::Error> {
+    let mut options = VariantOptions {
+        deprecated: variant
+            .attrs
+            .iter()
+            .any(|a| a.path().is_ident("deprecated")),
+        ..Default::default()
+    };
+    let mut result = Ok(());
+
+    variant.attrs.retain(|a| {
+        if !a.path().is_ident("bitmask") {
+            return true;
+        }
+        let parsed = a.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("group") {
+                let group: LitStr = meta.value()?.parse()?;
+                let value = group.value();
+                let valid = value.starts_with(|c: char| c.is_ascii_lowercase())
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                if !valid {
+                    return Err(syn::Error::new_spanned(
+                        &group,
+                        "group names have to be lowercase identifiers, e.g. \"io\"",
+                    ));
+                }
+                if !options.groups.iter().any(|g| g.value() == value) {
+                    options.groups.push(group);
+                }
+                Ok(())
+            } else {
+                let found = meta.path.get_ident().map(Ident::to_string);
+                Err(
+                    match found
+                        .as_deref()
+                        .and_then(|f| suggest(f, VARIANT_PROPERTIES))
                     {
-                        repr_type = Some(meta.path.get_ident().unwrap().clone())
-                    }
-                    Ok(())
-                })
-                .map_err(|_| false);
-
-            match res {
-                Ok(_) => repr_type,
-                Err(_) => None,
+                        Some(known) => meta.error(format!(
+                            "unsupported bitmask variant property, did you mean `{known}`?"
+                        )),
+                        None => meta.error("unsupported bitmask variant property"),
+                    },
+                )
             }
-        })
-        .ok_or(syn::Error::new(
-            Span::call_site(),
-            "Bitmasks require a explicitly defined representation. Please add #[repr(u32)], #[repr(u64)] etc. (automatic #[repr(C)] might be added in the future)",
-        ))
+        });
+        if let Err(e) = parsed {
+            match &mut result {
+                Ok(()) => result = Err(e),
+                Err(existing_error) => existing_error.combine(e),
+            }
+        }
+        false
+    });
+
+    match take_cfg_compounds(variant) {
+        Ok(cfg_compounds) => options.cfg_compounds = cfg_compounds,
+        Err(e) => match &mut result {
+            Ok(()) => result = Err(e),
+            Err(existing_error) => existing_error.combine(e),
+        },
+    }
+
+    result.map(|_| options)
+}
+
+/// Strips `#[cfg_attr(predicate, compound(...))]` attributes from a variant,
+/// returning each predicate with the `#[compound(...)]` it guards.
+fn take_cfg_compounds(
+    variant: &mut Variant,
+) -> Result<Vec<(proc_macro2::TokenStream, Attribute)>, syn::Error> {
+    let mut compounds = Vec::new();
+    let mut result = Ok(());
+
+    variant.attrs.retain(|a| {
+        if !a.path().is_ident("cfg_attr") {
+            return true;
+        }
+        let parsed = a.parse_args_with(|input: syn::parse::ParseStream| {
+            let predicate: Meta = input.parse()?;
+            input.parse::<Token![,]>()?;
+            let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+            Ok((predicate, attrs))
+        });
+        let (predicate, attrs) = match parsed {
+            Ok(parsed) => parsed,
+            // Left for the compiler to report
+            Err(_) => return true,
+        };
+        if !attrs.iter().any(|m| m.path().is_ident("compound")) {
+            return true;
+        }
+        if attrs.len() != 1 {
+            let e = syn::Error::new_spanned(
+                a,
+                "`compound` has to be the only attribute of its `cfg_attr`",
+            );
+            match &mut result {
+                Ok(()) => result = Err(e),
+                Err(existing_error) => existing_error.combine(e),
+            }
+            return false;
+        }
+        let compound = &attrs[0];
+        compounds.push((predicate.to_token_stream(), syn::parse_quote!(#[#compound])));
+        false
+    });
+
+    result.map(|_| compounds)
+}
+
+/// Helper attributes recognized on variants.
+const HELPER_ATTRIBUTES: &[&str] = &["compound", "bitmask"];
+
+/// Properties accepted by `#[bitmask(...)]` on variants.
+const VARIANT_PROPERTIES: &[&str] = &["skip", "group"];
+
+/// Rejects attributes that look like a misspelled helper attribute, which
+/// would otherwise be ignored and surface as an unrelated error.
+fn check_helper_attrs(variant: &Variant) -> Result<(), syn::Error> {
+    for attr in &variant.attrs {
+        let Some(ident) = attr.path().get_ident() else {
+            continue;
+        };
+        if let Some(known) = suggest(&ident.to_string(), HELPER_ATTRIBUTES) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("unknown attribute `{ident}`, did you mean `#[{known}(...)]`?"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the candidate `found` is most likely a typo of, if any.
+fn suggest<'a>(found: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|c| (*c, edit_distance(found, c)))
+        .filter(|(_, d)| *d > 0 && *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(c, _)| c)
 }
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+fn check_repr(attrs: &[Attribute]) -> Result<Ident, syn::Error> {
+    let mut repr_type: Option<Ident> = None;
+
+    // Every other repr argument, like `C` or `align(8)`, is left on the enum
+    // for the compiler to handle.
+    for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.input.peek(token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<proc_macro2::TokenStream>()?;
+                return Ok(());
+            }
+
+            let Some(ident) = meta.path.get_ident() else {
+                return Ok(());
+            };
+            if SIGNED_REPRS.iter().any(|r| ident == r) {
+                return Err(meta.error("Signed representations are not supported"));
+            }
+            if !UNSIGNED_REPRS.iter().any(|r| ident == r) {
+                return Ok(());
+            }
+            if let Some(previous) = &repr_type
+                && previous != ident
+            {
+                return Err(meta.error(format!(
+                    "Conflicting representations `{previous}` and `{ident}`"
+                )));
+            }
+            repr_type = Some(ident.clone());
+            Ok(())
+        })?;
+    }
+
+    repr_type.ok_or(syn::Error::new(
+        Span::call_site(),
+        "Bitmasks require a explicitly defined representation. Please add #[repr(u32)], #[repr(u64)] etc. (automatic #[repr(C)] might be added in the future)",
+    ))
+}
+
+const UNSIGNED_REPRS: [&str; 6] = ["u8", "u16", "u32", "u64", "u128", "usize"];
+const SIGNED_REPRS: [&str; 6] = ["i8", "i16", "i32", "i64", "i128", "isize"];
@@ -0,0 +1,108 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::derive::Context;
+
+/// A fixed-size table holding one value per variant, for per-flag data that
+/// would otherwise need a map keyed by the enum. Compounds and skipped variants
+/// get their own slot, so every variant is a key and lookups cannot fail.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        vis,
+        name,
+        inline,
+        variants,
+        ..
+    } = cx;
+
+    let map_name = format_ident!("{}Map", name);
+    let len = variants.len();
+    let indices = 0..len;
+
+    quote! {
+        #[doc = concat!(" A table holding a value for every [`", stringify!(#name), "`] variant.")]
+        ///
+        /// Compounds have their own slot, separate from the slots of the flags
+        /// they combine.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        #vis struct #map_name<T>([T; #len]);
+
+        impl<T> #map_name<T> {
+            /// Creates a table from the values of the variants in declaration
+            /// order, usable in constants.
+            #inline
+            pub const fn from_array(values: [T; #len]) -> Self {
+                Self(values)
+            }
+
+            /// Creates a table with the value returned by `f` for every variant.
+            #inline
+            pub fn from_fn(mut f: impl FnMut(#name) -> T) -> Self {
+                let variants = Self::variants();
+                Self(core::array::from_fn(|i| f(variants[i])))
+            }
+
+            #[allow(deprecated)]
+            #inline
+            const fn variants() -> [#name; #len] {
+                [#(#name::#variants),*]
+            }
+
+            #[allow(deprecated)]
+            #inline
+            const fn index(flag: #name) -> usize {
+                match flag {
+                    #(#name::#variants => #indices,)*
+                }
+            }
+
+            /// Returns the value of `flag`.
+            #inline
+            pub const fn get(&self, flag: #name) -> &T {
+                &self.0[Self::index(flag)]
+            }
+
+            /// Returns the value of `flag` mutably.
+            #inline
+            pub const fn get_mut(&mut self, flag: #name) -> &mut T {
+                &mut self.0[Self::index(flag)]
+            }
+
+            /// Iterates over the variants and their values, in declaration order.
+            #inline
+            pub fn iter(&self) -> impl Iterator<Item = (#name, &T)> {
+                Self::variants().into_iter().zip(self.0.iter())
+            }
+
+            /// Iterates over the variants and their values mutably, in
+            /// declaration order.
+            #inline
+            pub fn iter_mut(&mut self) -> impl Iterator<Item = (#name, &mut T)> {
+                Self::variants().into_iter().zip(self.0.iter_mut())
+            }
+        }
+
+        impl<T: Default> Default for #map_name<T> {
+            #inline
+            fn default() -> Self {
+                Self::from_fn(|_| T::default())
+            }
+        }
+
+        impl<T> core::ops::Index<#name> for #map_name<T> {
+            type Output = T;
+
+            #inline
+            fn index(&self, flag: #name) -> &T {
+                self.get(flag)
+            }
+        }
+
+        impl<T> core::ops::IndexMut<#name> for #map_name<T> {
+            #inline
+            fn index_mut(&mut self, flag: #name) -> &mut T {
+                self.get_mut(flag)
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Expr, Token,
+    parse::{Parse, ParseStream},
+};
+
+/// The input of `match_flags!`: the bits to dispatch on, then the arms.
+pub struct MatchFlags {
+    bits: Expr,
+    arms: TokenStream,
+}
+
+impl Parse for MatchFlags {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let bits = input.parse()?;
+        input.parse::<Token![,]>()?;
+        Ok(Self {
+            bits,
+            arms: input.parse()?,
+        })
+    }
+}
+
+/// Calls `for_each_flag` with a `match` over the arms, so the compiler checks
+/// that every variant is handled.
+pub fn expand(input: &MatchFlags) -> TokenStream {
+    let MatchFlags { bits, arms } = input;
+
+    quote! {
+        (#bits).for_each_flag(|flag| match flag {
+            #arms
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::derive::Context;
+
+/// Lookup of flags by their variant name, and parsing of the text written by
+/// `Display`.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        vis,
+        name,
+        bits_struct_name,
+        bits_type,
+        inline,
+        variants,
+        parsed,
+        ..
+    } = cx;
+
+    let name_error = format_ident!("{}NameError", name);
+    let names: Vec<String> = parsed.iter().map(|i| i.to_string()).collect();
+    let all_names = variants.iter().map(|i| i.to_string());
+    let message = format!(
+        "unknown {name} flag, expected one of: {}",
+        names
+            .iter()
+            .map(|n| format!("`{n}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    quote! {
+        impl<'a> core::convert::TryFrom<&'a str> for #name {
+            type Error = #name_error;
+
+            #[allow(deprecated)]
+            #inline
+            fn try_from(name: &'a str) -> Result<Self, #name_error> {
+                match name {
+                    #(#names => Ok(Self::#parsed),)*
+                    _ => Err(#name_error(())),
+                }
+            }
+        }
+
+        impl #name {
+            /// The listed variant with the given name, like `TryFrom<&str>`
+            /// but without an error to carry along.
+            #inline
+            pub fn from_name(name: &str) -> Option<Self> {
+                Self::try_from(name).ok()
+            }
+
+            /// The identifier of the variant, including skipped ones.
+            #[allow(deprecated)]
+            #inline
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #all_names,)*
+                }
+            }
+        }
+
+        impl #bits_struct_name {
+            /// The name of the listed flag, including compounds, whose value
+            /// is exactly `self`, or `None` if there is none.
+            #inline
+            pub const fn canonical_name(&self) -> Option<&'static str> {
+                let mut i = 0;
+                while i < Self::FLAG_ORDER.len() {
+                    let (flag, flag_name) = Self::FLAG_ORDER[i];
+                    if flag as #bits_type == self.0 {
+                        return Some(flag_name);
+                    }
+                    i += 1;
+                }
+                None
+            }
+
+            /// Combines the flags with the given variant names, e.g. from
+            /// already split command line arguments or a JSON array.
+            #inline
+            pub fn from_names<I>(names: I) -> Result<Self, #name_error>
+            where
+                I: IntoIterator,
+                I::Item: AsRef<str>,
+            {
+                let mut bits: #bits_type = 0;
+                for name in names {
+                    bits |= #bits_struct_name::from(#name::try_from(name.as_ref())?).0;
+                }
+                Ok(Self(bits))
+            }
+        }
+
+        impl #bits_struct_name {
+            /// Parses one `|`-separated part of the `Display` text.
+            #inline
+            fn parse_part(part: &str) -> Result<#bits_type, #name_error> {
+                let part = part.trim();
+                if part.starts_with(|c: char| c.is_ascii_digit()) {
+                    // Constant radixes keep `from_str_radix` from panicking
+                    let digits = part.get(2..).unwrap_or_default();
+                    let literal = match part.get(..2) {
+                        Some("0x" | "0X") => #bits_type::from_str_radix(digits, 16),
+                        Some("0o" | "0O") => #bits_type::from_str_radix(digits, 8),
+                        Some("0b" | "0B") => #bits_type::from_str_radix(digits, 2),
+                        _ => part.parse::<#bits_type>(),
+                    };
+                    literal.map_err(|_| #name_error(()))
+                } else {
+                    Ok(#bits_struct_name::from(#name::try_from(part)?).0)
+                }
+            }
+
+            /// Parses like `FromStr`, but skips the parts that are neither a
+            /// flag name nor an integer literal instead of failing, and returns
+            /// them trimmed, e.g. to warn about them. Empty parts are ignored.
+            #inline
+            pub fn from_str_lossy(s: &str) -> (Self, impl Iterator<Item = &str>) {
+                let mut bits: #bits_type = 0;
+                for part in s.split('|') {
+                    if let Ok(part_bits) = Self::parse_part(part) {
+                        bits |= part_bits;
+                    }
+                }
+
+                let unknown = s
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty() && Self::parse_part(part).is_err());
+                (Self(bits), unknown)
+            }
+        }
+
+        impl core::str::FromStr for #bits_struct_name {
+            type Err = #name_error;
+
+            /// Parses flag names separated by `|`, as written by `Display`.
+            /// Integer literals, with a `0x`, `0o` or `0b` prefix or in
+            /// decimal, are taken as raw bits, so values with bits not covered
+            /// by any flag round-trip.
+            #inline
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut bits: #bits_type = 0;
+                if s.trim().is_empty() {
+                    return Ok(Self(bits));
+                }
+
+                for part in s.split('|') {
+                    bits |= Self::parse_part(part)?;
+                }
+                Ok(Self(bits))
+            }
+        }
+
+        /// Error returned when a string is not the name of a flag.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #vis struct #name_error(());
+
+        impl core::fmt::Display for #name_error {
+            #inline
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(#message)
+            }
+        }
+
+        impl core::error::Error for #name_error {}
+    }
+}
+
+/// Converts a `CamelCase` variant or enum name into `snake_case`.
+pub fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
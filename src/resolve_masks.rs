@@ -1,134 +1,283 @@
 use std::collections::HashMap;
 
-use syn::{Attribute, Expr, ExprBinary, Ident, Variant};
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{Attribute, Expr, ExprBinary, ExprPath, Ident, Variant};
+
+/// A value a variant resolves to, which applies when all of the `cfg`
+/// predicates hold. Variants that do not depend on a `cfg_attr` compound
+/// resolve to a single value without predicates.
+#[derive(Clone)]
+pub struct Resolved {
+    pub cfg: Vec<TokenStream>,
+    pub expr: Expr,
+}
+
+impl Resolved {
+    pub fn new(expr: Expr) -> Self {
+        Self {
+            cfg: Vec::new(),
+            expr,
+        }
+    }
+}
+
+/// The definition of a variant with a `#[compound(...)]` attribute.
+#[derive(Default)]
+pub struct Compound {
+    /// `#[cfg_attr(predicate, compound(...))]` attributes, the first one whose
+    /// predicate holds is used.
+    pub branches: Vec<(TokenStream, Attribute)>,
+    /// Used when no branch applies.
+    pub fallback: Option<Fallback>,
+}
+
+pub enum Fallback {
+    Compound(Attribute),
+    Value(Expr),
+}
 
 pub fn resolve_variant(
     i: usize,
-    attr: Attribute,
-    variants: &mut [Variant],
-    resolved_values: &mut HashMap<Ident, Expr>,
+    compounds: &[Option<Compound>],
+    enum_ident: &Ident,
+    variants: &[Variant],
+    resolved_values: &mut HashMap<Ident, Vec<Resolved>>,
     computed_idents: &mut Vec<Ident>,
-) -> Result<Expr, syn::Error> {
-    let ident = &variants[i].ident.clone();
-    if computed_idents.iter().find(|i| *ident == **i).is_some() {
+) -> Result<Vec<Resolved>, syn::Error> {
+    let ident = &variants[i].ident;
+    if let Some(resolved) = resolved_values.get(ident) {
+        return Ok(resolved.clone());
+    }
+
+    if computed_idents.contains(ident) {
         return Err(syn::Error::new_spanned(
             ident,
             "Infinite recursion detected",
         ));
     }
+    let Some(compound) = &compounds[i] else {
+        return Err(syn::Error::new_spanned(ident, "Variant has no value"));
+    };
 
     computed_idents.push(ident.clone());
 
-    if let Some(expr) = resolved_values.get(ident) {
-        return Ok(expr.clone());
+    let predicates: Vec<_> = compound.branches.iter().map(|(p, _)| p).collect();
+    let mut resolved = Vec::new();
+    for (n, (predicate, attr)) in compound.branches.iter().enumerate() {
+        let mut cfg = vec![predicate.clone()];
+        if n > 0 {
+            let earlier = &predicates[..n];
+            cfg.push(quote!(not(any(#(#earlier),*))));
+        }
+        resolved.extend(with_cfg(
+            &cfg,
+            resolve_expr(
+                parse_compound(attr)?,
+                attr,
+                compounds,
+                enum_ident,
+                variants,
+                resolved_values,
+                computed_idents,
+            )?,
+        ));
     }
 
-    let expr = resolve_expr(
-        parse_compound(&attr)?,
-        ident,
-        attr.clone(),
-        variants,
-        resolved_values,
-        computed_idents,
-    )?;
-
-    resolved_values.insert(ident.clone(), expr.clone());
-    variants[i].discriminant = Some((Default::default(), expr.clone()));
-    Ok(expr)
+    let otherwise: Vec<TokenStream> = if predicates.is_empty() {
+        Vec::new()
+    } else {
+        vec![quote!(not(any(#(#predicates),*)))]
+    };
+    match &compound.fallback {
+        Some(Fallback::Compound(attr)) => resolved.extend(with_cfg(
+            &otherwise,
+            resolve_expr(
+                parse_compound(attr)?,
+                attr,
+                compounds,
+                enum_ident,
+                variants,
+                resolved_values,
+                computed_idents,
+            )?,
+        )),
+        Some(Fallback::Value(expr)) => resolved.push(Resolved {
+            cfg: otherwise,
+            expr: expr.clone(),
+        }),
+        None => {
+            let message = format!(
+                "no #[cfg_attr(..., compound(...))] of `{ident}` applies to this configuration, and it has no fallback value"
+            );
+            resolved.push(Resolved {
+                cfg: otherwise,
+                expr: syn::parse_quote!({
+                    ::core::compile_error!(#message);
+                    0
+                }),
+            });
+        }
+    }
+
+    computed_idents.pop();
+    resolved_values.insert(ident.clone(), resolved.clone());
+    Ok(resolved)
+}
+
+fn with_cfg(cfg: &[TokenStream], resolved: Vec<Resolved>) -> impl Iterator<Item = Resolved> {
+    resolved.into_iter().map(move |r| Resolved {
+        cfg: cfg.iter().cloned().chain(r.cfg).collect(),
+        expr: r.expr,
+    })
+}
+
+fn map(resolved: Vec<Resolved>, f: impl Fn(&Expr) -> Expr) -> Vec<Resolved> {
+    resolved
+        .into_iter()
+        .map(|r| Resolved {
+            expr: f(&r.expr),
+            cfg: r.cfg,
+        })
+        .collect()
 }
 
+/// Replaces the variant references in `expr` with their values. Unsupported
+/// expressions are reported at `source`.
 pub fn resolve_expr(
     expr: Expr,
-    target_ident: &Ident,
-    attr: Attribute,
-    variants: &mut [Variant],
-    resolved_values: &mut HashMap<Ident, Expr>,
+    source: &dyn ToTokens,
+    compounds: &[Option<Compound>],
+    enum_ident: &Ident,
+    variants: &[Variant],
+    resolved_values: &mut HashMap<Ident, Vec<Resolved>>,
     computed_idents: &mut Vec<Ident>,
-) -> Result<Expr, syn::Error> {
+) -> Result<Vec<Resolved>, syn::Error> {
     match expr {
         Expr::Binary(ExprBinary {
             left, op, right, ..
         }) => {
-            // if !matches!(op, BinOp::BitOr(_)) {
-            //     panic!("only | is supported in #[compound]");
-            // }
-
             let left = resolve_expr(
                 *left,
-                target_ident,
-                attr.clone(),
+                source,
+                compounds,
+                enum_ident,
                 variants,
                 resolved_values,
                 computed_idents,
             )?;
             let right = resolve_expr(
                 *right,
-                target_ident,
-                attr.clone(),
+                source,
+                compounds,
+                enum_ident,
                 variants,
                 resolved_values,
                 computed_idents,
             )?;
 
-            let expr: Expr = syn::parse_quote!(#left #op #right);
-            Ok(expr)
+            // Every combination of the values of both sides
+            let mut resolved = Vec::new();
+            for l in &left {
+                for r in &right {
+                    let (le, re) = (&l.expr, &r.expr);
+                    resolved.push(Resolved {
+                        cfg: l.cfg.iter().chain(&r.cfg).cloned().collect(),
+                        expr: syn::parse_quote!(#le #op #re),
+                    });
+                }
+            }
+            Ok(resolved)
         }
 
         Expr::Path(p) => {
-            let ident = p
-                .path
-                .get_ident()
-                .ok_or(syn::Error::new_spanned(&p, "Expected expression"))?;
+            let ident = variant_ident(&p, enum_ident).ok_or(syn::Error::new_spanned(
+                &p,
+                format!("Expected a variant of {enum_ident}: `Variant`, `Self::Variant` or `{enum_ident}::Variant`"),
+            ))?;
 
             let i =
                 variants
                     .iter()
                     .position(|v| v.ident == *ident)
                     .ok_or(syn::Error::new_spanned(
-                        &ident,
-                        &format!("No field found with name: {ident}"),
+                        ident,
+                        format!("No field found with name: {ident}"),
                     ))?;
 
-            resolve_variant(i, attr.clone(), variants, resolved_values, computed_idents)
+            let resolved = resolve_variant(
+                i,
+                compounds,
+                enum_ident,
+                variants,
+                resolved_values,
+                computed_idents,
+            )?;
+            // Keep the referenced value together when it is an operation
+            Ok(map(resolved, |e| syn::parse_quote!((#e))))
         }
 
-        Expr::Lit(_) => Ok(expr),
+        Expr::Lit(_) => Ok(vec![Resolved::new(expr)]),
 
         Expr::Paren(paren) => {
             let inner_resolved = resolve_expr(
                 *paren.expr,
-                target_ident,
-                attr,
+                source,
+                compounds,
+                enum_ident,
                 variants,
                 resolved_values,
                 computed_idents,
             )?;
 
-            Ok(inner_resolved)
+            Ok(map(inner_resolved, |e| syn::parse_quote!((#e))))
         }
 
         Expr::Unary(u) => {
             let inner = resolve_expr(
                 *u.expr,
-                target_ident,
-                attr,
+                source,
+                compounds,
+                enum_ident,
                 variants,
                 resolved_values,
                 computed_idents,
             )?;
             let op = u.op;
-            Ok(syn::parse_quote!(#op #inner))
+            Ok(map(inner, |e| syn::parse_quote!(#op #e)))
         }
 
-        _ => Err(syn::Error::new_spanned(&attr, "Unsupported expression")),
+        _ => Err(syn::Error::new_spanned(source, "Unsupported expression")),
+    }
+}
+
+/// Accepts `Variant`, `Self::Variant` and `EnumName::Variant` references.
+fn variant_ident<'a>(p: &'a ExprPath, enum_ident: &Ident) -> Option<&'a Ident> {
+    if p.qself.is_some() {
+        return None;
+    }
+
+    let segments = &p.path.segments;
+    if segments.iter().any(|s| !s.arguments.is_none()) {
+        return None;
+    }
+
+    match segments.len() {
+        1 if p.path.leading_colon.is_none() => Some(&segments[0].ident),
+        2 if p.path.leading_colon.is_none()
+            && (segments[0].ident == "Self" || segments[0].ident == *enum_ident) =>
+        {
+            Some(&segments[1].ident)
+        }
+        _ => None,
     }
 }
 
 fn parse_compound(attr: &Attribute) -> Result<Expr, syn::Error> {
     attr.parse_args::<Expr>().map_err(|e| {
         syn::Error::new_spanned(
-            &attr,
-            &format!(
+            attr,
+            format!(
                 "parsing attribute ({:?}) failed with error: {:?}",
                 attr.path().get_ident(),
                 e
@@ -136,3 +285,11 @@ fn parse_compound(attr: &Attribute) -> Result<Expr, syn::Error> {
         )
     })
 }
+
+/// Builds the union of the given discriminant expressions.
+pub fn union_expr<'a>(exprs: impl Iterator<Item = &'a Expr>) -> Expr {
+    exprs.fold(
+        syn::parse_quote!(0),
+        |acc, expr| syn::parse_quote!(#acc | (#expr)),
+    )
+}
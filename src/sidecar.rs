@@ -0,0 +1,111 @@
+use std::{fmt::Write, path::PathBuf};
+
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{Ident, ItemEnum, LitStr};
+
+use crate::{
+    VariantOptions,
+    eval::{eval, repr_width},
+};
+
+/// Where the sidecar description of a bitmask is written to.
+pub enum Sidecar {
+    OutDir,
+    Path(LitStr),
+}
+
+/// Writes a JSON description of the resolved bitmask into
+/// `<dir>/<EnumName>.bitmask.json`.
+pub fn write_sidecar(
+    target: &Sidecar,
+    input: &ItemEnum,
+    repr: &Ident,
+    variant_options: &[VariantOptions],
+) -> Result<(), syn::Error> {
+    let (dir, span) = match target {
+        Sidecar::OutDir => {
+            let Some(out_dir) = std::env::var_os("OUT_DIR") else {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "OUT_DIR is not set, add a build script or use #[bitmask(sidecar = \"path\")]",
+                ));
+            };
+            (PathBuf::from(out_dir), Span::call_site())
+        }
+        Sidecar::Path(path) => {
+            let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+            (PathBuf::from(manifest_dir).join(path.value()), path.span())
+        }
+    };
+
+    let file = dir.join(format!("{}.bitmask.json", input.ident));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&file, describe(input, repr, variant_options)))
+        .map_err(|e| {
+            syn::Error::new(
+                span,
+                format!("failed to write bitmask sidecar {}: {e}", file.display()),
+            )
+        })
+}
+
+fn describe(input: &ItemEnum, repr: &Ident, variant_options: &[VariantOptions]) -> String {
+    let width = repr_width(repr);
+    let mut json = String::new();
+
+    json.push_str("{\n");
+    let _ = writeln!(json, "  \"name\": {},", string(&input.ident.to_string()));
+    let _ = writeln!(json, "  \"repr\": {},", string(&repr.to_string()));
+    json.push_str("  \"flags\": [");
+
+    for (i, (variant, options)) in input.variants.iter().zip(variant_options).enumerate() {
+        // Variants depending on a `cfg_attr` compound have no single value
+        let (value, expr) = match &variant.discriminant {
+            Some((_, expr)) => (
+                match eval(expr, width) {
+                    Some(value) => string(&format!("{value:#x}")),
+                    None => "null".to_string(),
+                },
+                string(&expr.to_token_stream().to_string()),
+            ),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let compound = match options.compound_source() {
+            Some(source) => string(&source),
+            None => "null".to_string(),
+        };
+
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        let _ = write!(
+            json,
+            "    {{ \"name\": {}, \"value\": {}, \"expr\": {}, \"compound\": {}, \"skip\": {}, \"deprecated\": {} }}",
+            string(&variant.ident.to_string()),
+            value,
+            expr,
+            compound,
+            options.skip,
+            options.deprecated,
+        );
+    }
+
+    json.push_str("\n  ]\n}\n");
+    json
+}
+
+fn string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,48 @@
+//! Support for `#[bitmask(subset_of = Parent)]`.
+//!
+//! Variants of a subset take their value from the variant of the same name in
+//! the parent bitmask, so the subset converts into the parent losslessly.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, Ident, Path, Variant};
+
+use crate::extends::bits_path;
+
+/// The value of a subset variant, taken from the parent.
+pub fn value(parent: &Path, variant: &Variant, bits_type: &Ident) -> Expr {
+    let ident = &variant.ident;
+    syn::parse_quote!(#parent::#ident as #bits_type)
+}
+
+/// Conversions from the subset into the parent's bits type.
+pub fn conversions(
+    parent: &Path,
+    name: &Ident,
+    bits_struct_name: &Ident,
+    bits_type: &Ident,
+    inline: &TokenStream,
+) -> TokenStream {
+    let parent_bits = bits_path(parent);
+
+    quote! {
+        const _: () = assert!(
+            core::mem::size_of::<#name>() <= core::mem::size_of::<#parent>(),
+            concat!(stringify!(#name), " must not have a wider representation than its parent"),
+        );
+
+        impl core::convert::From<#name> for #parent_bits {
+            #inline
+            fn from(val: #name) -> Self {
+                #parent_bits::from_bits_retain(val as #bits_type as _)
+            }
+        }
+
+        impl core::convert::From<#bits_struct_name> for #parent_bits {
+            #inline
+            fn from(val: #bits_struct_name) -> Self {
+                #parent_bits::from_bits_retain(val.0 as _)
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use quote::{ToTokens, quote};
+use syn::{Attribute, Expr, ImplItem, Item};
+
+/// Names of the generated functions that lack `#[inline(never)]`, looking
+/// into impls, modules and `const _: () = { ... }` blocks.
+fn missing_inline(items: &[Item], missing: &mut Vec<String>) {
+    let has_inline = |attrs: &[Attribute]| {
+        attrs.iter().any(|attr| {
+            attr.path().is_ident("inline")
+                && attr.meta.to_token_stream().to_string() == "inline (never)"
+        })
+    };
+
+    for item in items {
+        match item {
+            Item::Impl(imp) => {
+                let self_ty = imp.self_ty.to_token_stream().to_string();
+                for impl_item in &imp.items {
+                    if let ImplItem::Fn(f) = impl_item
+                        && !has_inline(&f.attrs)
+                    {
+                        missing.push(format!("{self_ty}::{}", f.sig.ident));
+                    }
+                }
+            }
+            Item::Fn(f) if !has_inline(&f.attrs) => missing.push(f.sig.ident.to_string()),
+            Item::Mod(m) => {
+                if let Some((_, items)) = &m.content {
+                    missing_inline(items, missing);
+                }
+            }
+            Item::Const(c) => {
+                if let Expr::Block(block) = &*c.expr {
+                    let items: Vec<Item> = block
+                        .block
+                        .stmts
+                        .iter()
+                        .filter_map(|stmt| match stmt {
+                            syn::Stmt::Item(item) => Some(item.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    missing_inline(&items, missing);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn inline_applies_to_every_generated_function() {
+    let expanded = crate::expand(
+        quote!(
+            inline = "never",
+            default = Read,
+            all_combinations,
+            tracked,
+            stable_abi
+        ),
+        quote! {
+            #[repr(u8)]
+            pub enum Permissions {
+                #[bitmask(group = "access")]
+                Read = 0b001,
+                #[bitmask(group = "access")]
+                Write = 0b010,
+                Exec = 0b100,
+                #[compound(Read | Write)]
+                ReadWrite,
+            }
+        },
+    );
+    let file: syn::File = syn::parse2(expanded).expect("the expansion parses");
+
+    let mut missing = Vec::new();
+    missing_inline(&file.items, &mut missing);
+    assert!(
+        missing.is_empty(),
+        "missing `#[inline(never)]`: {missing:#?}"
+    );
+}
@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::derive::Context;
+
+/// A wrapper around the bits type reporting which bits changed since the
+/// changes were last taken, for `#[bitmask(tracked)]`.
+pub fn impls(cx: &Context) -> TokenStream {
+    let Context {
+        vis,
+        name,
+        bits_struct_name,
+        inline,
+        ..
+    } = cx;
+
+    let tracked_name = format_ident!("Tracked{}", bits_struct_name);
+    let changes_name = format_ident!("{}Changes", name);
+
+    quote! {
+        #[doc = concat!(" Bits that changed in a [`", stringify!(#tracked_name), "`].")]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #vis struct #changes_name {
+            /// Bits that are set now, but were not before.
+            pub inserted: #bits_struct_name,
+            /// Bits that were set before, but are not now.
+            pub removed: #bits_struct_name,
+        }
+
+        impl #changes_name {
+            /// Returns `true` if no bit changed.
+            #inline
+            pub const fn is_empty(&self) -> bool {
+                self.inserted.0 == 0 && self.removed.0 == 0
+            }
+        }
+
+        #[doc = concat!(" A [`", stringify!(#bits_struct_name), "`] that keeps track of the bits changed since [`", stringify!(#tracked_name), "::take_changes`] was last called.")]
+        ///
+        /// Changes are the difference to the value at that point, so setting
+        /// and clearing a bit in between is no change.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #vis struct #tracked_name {
+            bits: #bits_struct_name,
+            baseline: #bits_struct_name,
+        }
+
+        impl #tracked_name {
+            /// Starts tracking `bits`, without any changes.
+            #inline
+            pub const fn new(bits: #bits_struct_name) -> Self {
+                Self {
+                    bits,
+                    baseline: bits,
+                }
+            }
+
+            /// Returns the current value.
+            #inline
+            pub const fn get(&self) -> #bits_struct_name {
+                self.bits
+            }
+
+            /// Returns the current value for modification. Every change made
+            /// through it is tracked.
+            #inline
+            pub fn get_mut(&mut self) -> &mut #bits_struct_name {
+                &mut self.bits
+            }
+
+            /// Sets the bits of `flags`.
+            #inline
+            pub fn insert(&mut self, flags: impl Into<#bits_struct_name>) {
+                self.bits |= flags.into();
+            }
+
+            /// Clears the bits of `flags`.
+            #inline
+            pub fn remove(&mut self, flags: impl Into<#bits_struct_name>) {
+                self.bits -= flags.into();
+            }
+
+            /// Replaces the current value.
+            #inline
+            pub fn replace(&mut self, bits: #bits_struct_name) {
+                self.bits = bits;
+            }
+
+            /// Returns `true` if the value differs from the one at the last
+            /// [`Self::take_changes`].
+            #inline
+            pub const fn has_changes(&self) -> bool {
+                self.bits.0 != self.baseline.0
+            }
+
+            /// Returns the changes since the last call, or since
+            /// [`Self::new`], and starts tracking from the current value.
+            #inline
+            pub fn take_changes(&mut self) -> #changes_name {
+                let changes = #changes_name {
+                    inserted: #bits_struct_name(self.bits.0 & !self.baseline.0),
+                    removed: #bits_struct_name(self.baseline.0 & !self.bits.0),
+                };
+                self.baseline = self.bits;
+                changes
+            }
+        }
+
+        impl core::convert::From<#bits_struct_name> for #tracked_name {
+            #inline
+            fn from(bits: #bits_struct_name) -> Self {
+                Self::new(bits)
+            }
+        }
+    }
+}
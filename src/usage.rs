@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Variant};
+
+use crate::{
+    VariantOptions,
+    derive::Context,
+    eval::{eval, repr_width},
+};
+
+/// Constants showing how much of the representation is in use, for
+/// `#[bitmask(report_usage)]`.
+pub fn impls(cx: &Context, variants: &[(&Variant, &VariantOptions)]) -> TokenStream {
+    let Context {
+        name,
+        bits_struct_name,
+        bits_type,
+        repr,
+        variants: all_variants,
+        ..
+    } = cx;
+
+    let summary = summary(repr, variants)
+        .map(|summary| format!(" {summary}"))
+        .unwrap_or_else(|| " The values are only known to the compiler.".to_string());
+
+    quote! {
+        impl #bits_struct_name {
+            /// The bits used by any variant, including skipped and deprecated
+            /// ones.
+            ///
+            #[doc = #summary]
+            #[allow(deprecated)]
+            pub const USED_BITS: Self = Self(0 #(| (#name::#all_variants as #bits_type))*);
+
+            /// The bits of the representation no variant uses.
+            pub const FREE_BITS: Self = Self(!Self::USED_BITS.0);
+        }
+    }
+}
+
+/// Describes the used and free bits, if every value can be evaluated by the
+/// macro. Values that depend on `cfg` count in every configuration.
+fn summary(repr: &Ident, variants: &[(&Variant, &VariantOptions)]) -> Option<String> {
+    let width = repr_width(repr);
+    let mut used = 0u128;
+    for (variant, options) in variants {
+        match &variant.discriminant {
+            Some((_, expr)) => used |= eval(expr, width)?,
+            None => {
+                for value in &options.cfg_values {
+                    used |= eval(&value.expr, width)?;
+                }
+            }
+        }
+    }
+
+    let Some(width) = width else {
+        return Some(format!("Uses bits {}.", ranges(used, 128)));
+    };
+    let free = !used & (u128::MAX >> (128 - width));
+    Some(format!(
+        "Uses {} of {width} bits: {}. Free: {}.",
+        used.count_ones(),
+        ranges(used, width),
+        ranges(free, width),
+    ))
+}
+
+/// Lists the set bits as ranges of indices, like `0-2, 5`.
+fn ranges(bits: u128, width: u32) -> String {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < width {
+        if bits >> i & 1 == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < width && bits >> (i + 1) & 1 == 1 {
+            i += 1;
+        }
+        ranges.push(if start == i {
+            start.to_string()
+        } else {
+            format!("{start}-{i}")
+        });
+        i += 1;
+    }
+
+    if ranges.is_empty() {
+        "none".to_string()
+    } else {
+        ranges.join(", ")
+    }
+}
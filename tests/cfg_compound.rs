@@ -0,0 +1,68 @@
+use enum_bitmasks::bitmask;
+
+// `all()` always holds and `any()` never does, standing in for platform
+// predicates
+
+#[bitmask]
+#[repr(u8)]
+pub enum Active {
+    A = 0b0001,
+    B = 0b0010,
+    #[cfg_attr(all(), compound(0b1000))]
+    #[compound(0b0100)]
+    Extra,
+    #[compound(B | Extra)]
+    Derived,
+}
+
+#[bitmask]
+#[repr(u8)]
+pub enum Inactive {
+    A = 0b0001,
+    B = 0b0010,
+    #[cfg_attr(any(), compound(0b1000))]
+    #[compound(0b0100)]
+    Extra,
+    #[compound(B | Extra)]
+    Derived,
+}
+
+#[bitmask]
+#[repr(u8)]
+pub enum FirstActive {
+    A = 0b0001,
+    #[cfg_attr(any(), compound(0b0010))]
+    #[cfg_attr(all(), compound(0b0100))]
+    #[cfg_attr(all(), compound(0b1000))]
+    Extra,
+}
+
+#[test]
+fn active_cfg_attr_decides_the_value() {
+    assert_eq!(Active::Extra as u8, 0b1000);
+    assert_eq!(Active::Derived as u8, 0b1010);
+    assert_eq!(u8::from(ActiveBits::ALL), 0b1011);
+    assert_eq!(
+        Active::VARIANTS,
+        [Active::A, Active::B, Active::Extra, Active::Derived]
+    );
+    assert_eq!(Active::from_repr(0b1000), Some(Active::Extra));
+}
+
+#[test]
+fn inactive_cfg_attr_falls_back() {
+    assert_eq!(Inactive::Extra as u8, 0b0100);
+    assert_eq!(Inactive::Derived as u8, 0b0110);
+    assert_eq!(u8::from(InactiveBits::ALL), 0b0111);
+    assert_eq!(
+        Inactive::VARIANTS,
+        [Inactive::A, Inactive::B, Inactive::Extra, Inactive::Derived]
+    );
+    assert_eq!(Inactive::from_repr(0b1000), None);
+}
+
+#[test]
+fn first_active_cfg_attr_wins() {
+    assert_eq!(FirstActive::Extra as u8, 0b0100);
+    assert_eq!(u8::from(FirstActiveBits::ALL), 0b0101);
+}
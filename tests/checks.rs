@@ -0,0 +1,28 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b01,
+    Write = 0b10,
+}
+
+const fn low_16_bits(value: u32) -> bool {
+    value >> 16 == 0
+}
+
+#[bitmask(validate = low_16_bits)]
+#[repr(u32)]
+pub enum Irq {
+    Timer = 1 << 0,
+    Uart = 1 << 15,
+}
+
+const READ: PermissionsBits = PermissionsBits::from_bits_retain(Permissions::Read as u8);
+const DEFAULT_MODE: PermissionsBits = PermissionsBits::ALL.assert_contains(READ);
+
+#[test]
+fn returns_the_checked_value() {
+    assert_eq!(DEFAULT_MODE, PermissionsBits::ALL);
+    assert_eq!(u32::from(IrqBits::ALL), 1 | 1 << 15);
+}
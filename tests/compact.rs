@@ -0,0 +1,97 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Small {
+    A = 0b01,
+    B = 0b10,
+}
+
+#[bitmask]
+#[repr(u128)]
+pub enum Wide {
+    Low = 1,
+    Mid = 1 << 64,
+    High = 1 << 127,
+}
+
+#[test]
+fn decodes_big_endian_bytes_without_leading_zeros() {
+    assert_eq!(WideBits::from_compact_string(""), Some(WideBits::EMPTY));
+    assert_eq!(
+        WideBits::from_compact_string("AQ"),
+        Some(WideBits::from(Wide::Low))
+    );
+    // 0x01 0x02 0x03
+    assert_eq!(
+        WideBits::from_compact_string("AQID"),
+        Some(WideBits::from_bits_retain(0x010203))
+    );
+}
+
+#[test]
+fn keeps_unknown_bits() {
+    assert_eq!(
+        SmallBits::from_compact_string("gA"),
+        Some(SmallBits::from_bits_retain(0x80))
+    );
+}
+
+#[test]
+fn rejects_truncated_and_malformed_input() {
+    // A lone character does not complete a byte
+    assert_eq!(WideBits::from_compact_string("AQIDB"), None);
+    assert_eq!(WideBits::from_compact_string("A"), None);
+    // Padding bits that were never written
+    assert_eq!(WideBits::from_compact_string("AR"), None);
+    // Not URL-safe base64, or padded
+    assert_eq!(WideBits::from_compact_string("AQ=="), None);
+    assert_eq!(WideBits::from_compact_string("+w"), None);
+}
+
+#[test]
+fn rejects_values_wider_than_the_representation() {
+    // Two bytes for a `u8`
+    assert_eq!(SmallBits::from_compact_string("AQI"), None);
+    // 17 bytes for a `u128`
+    assert_eq!(
+        WideBits::from_compact_string("AQAAAAAAAAAAAAAAAAAAAAA"),
+        None
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn round_trips() {
+    let values = [
+        WideBits::EMPTY,
+        WideBits::ALL,
+        Wide::Low | Wide::High,
+        WideBits::from(Wide::Mid),
+        WideBits::from_bits_retain(u128::MAX),
+        WideBits::from_bits_retain(0xdead_beef),
+    ];
+    for bits in values {
+        let encoded = bits.to_compact_string();
+        assert_eq!(
+            WideBits::from_compact_string(&encoded),
+            Some(bits),
+            "{encoded}"
+        );
+    }
+
+    assert_eq!(WideBits::EMPTY.to_compact_string(), "");
+    assert_eq!(
+        WideBits::from_bits_retain(u128::MAX)
+            .to_compact_string()
+            .len(),
+        22
+    );
+    for raw in 0..=u8::MAX {
+        let bits = SmallBits::from_bits_retain(raw);
+        assert_eq!(
+            SmallBits::from_compact_string(&bits.to_compact_string()),
+            Some(bits)
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+// `Middle` is the widest compound, but the smallest cover of every bit is
+// `Low | High`
+#[bitmask]
+#[repr(u8)]
+pub enum Overlapping {
+    B1 = 1 << 0,
+    B2 = 1 << 1,
+    B3 = 1 << 2,
+    B4 = 1 << 3,
+    B5 = 1 << 4,
+    B6 = 1 << 5,
+    #[compound(B2 | B3 | B4 | B5)]
+    Middle,
+    #[compound(B1 | B2 | B3)]
+    Low,
+    #[compound(B4 | B5 | B6)]
+    High,
+}
+
+// Many narrow compounds overlapping in every way, for which trying every
+// combination would not finish
+#[bitmask]
+#[repr(u64)]
+pub enum Tangled {
+    S0 = 1 << 0,
+    S1 = 1 << 1,
+    S2 = 1 << 2,
+    S3 = 1 << 3,
+    S4 = 1 << 4,
+    S5 = 1 << 5,
+    S6 = 1 << 6,
+    S7 = 1 << 7,
+    S8 = 1 << 8,
+    S9 = 1 << 9,
+    S10 = 1 << 10,
+    S11 = 1 << 11,
+    S12 = 1 << 12,
+    S13 = 1 << 13,
+    S14 = 1 << 14,
+    S15 = 1 << 15,
+    S16 = 1 << 16,
+    S17 = 1 << 17,
+    S18 = 1 << 18,
+    S19 = 1 << 19,
+    S20 = 1 << 20,
+    S21 = 1 << 21,
+    S22 = 1 << 22,
+    S23 = 1 << 23,
+    S24 = 1 << 24,
+    S25 = 1 << 25,
+    S26 = 1 << 26,
+    S27 = 1 << 27,
+    S28 = 1 << 28,
+    S29 = 1 << 29,
+    S30 = 1 << 30,
+    S31 = 1 << 31,
+    S32 = 1 << 32,
+    S33 = 1 << 33,
+    S34 = 1 << 34,
+    S35 = 1 << 35,
+    S36 = 1 << 36,
+    S37 = 1 << 37,
+    S38 = 1 << 38,
+    S39 = 1 << 39,
+    S40 = 1 << 40,
+    S41 = 1 << 41,
+    S42 = 1 << 42,
+    S43 = 1 << 43,
+    S44 = 1 << 44,
+    S45 = 1 << 45,
+    S46 = 1 << 46,
+    S47 = 1 << 47,
+    S48 = 1 << 48,
+    S49 = 1 << 49,
+    S50 = 1 << 50,
+    S51 = 1 << 51,
+    S52 = 1 << 52,
+    S53 = 1 << 53,
+    S54 = 1 << 54,
+    S55 = 1 << 55,
+    S56 = 1 << 56,
+    S57 = 1 << 57,
+    S58 = 1 << 58,
+    S59 = 1 << 59,
+    S60 = 1 << 60,
+    S61 = 1 << 61,
+    S62 = 1 << 62,
+    S63 = 1 << 63,
+    #[compound(S24 | S57 | S59)]
+    C0,
+    #[compound(S23 | S60)]
+    C1,
+    #[compound(S38 | S57)]
+    C2,
+    #[compound(S5 | S11)]
+    C3,
+    #[compound(S1 | S20 | S57)]
+    C4,
+    #[compound(S4 | S7)]
+    C5,
+    #[compound(S3 | S30)]
+    C6,
+    #[compound(S25 | S41 | S56)]
+    C7,
+    #[compound(S37 | S63)]
+    C8,
+    #[compound(S10 | S58)]
+    C9,
+    #[compound(S10 | S32 | S52)]
+    C10,
+    #[compound(S3 | S29 | S36)]
+    C11,
+    #[compound(S13 | S51)]
+    C12,
+    #[compound(S37 | S49)]
+    C13,
+    #[compound(S0 | S2)]
+    C14,
+    #[compound(S6 | S26)]
+    C15,
+    #[compound(S48 | S50 | S53)]
+    C16,
+    #[compound(S25 | S34)]
+    C17,
+    #[compound(S11 | S39 | S42)]
+    C18,
+    #[compound(S15 | S52)]
+    C19,
+    #[compound(S12 | S31)]
+    C20,
+    #[compound(S7 | S59)]
+    C21,
+    #[compound(S22 | S24 | S57)]
+    C22,
+    #[compound(S16 | S53)]
+    C23,
+    #[compound(S14 | S50 | S53)]
+    C24,
+    #[compound(S0 | S34)]
+    C25,
+    #[compound(S2 | S23 | S26)]
+    C26,
+    #[compound(S5 | S12 | S18)]
+    C27,
+    #[compound(S33 | S56)]
+    C28,
+    #[compound(S37 | S42)]
+    C29,
+    #[compound(S9 | S11 | S26)]
+    C30,
+    #[compound(S1 | S47)]
+    C31,
+    #[compound(S16 | S58 | S61)]
+    C32,
+    #[compound(S23 | S49)]
+    C33,
+    #[compound(S29 | S39)]
+    C34,
+    #[compound(S20 | S24)]
+    C35,
+    #[compound(S49 | S61)]
+    C36,
+    #[compound(S6 | S53)]
+    C37,
+    #[compound(S4 | S13)]
+    C38,
+    #[compound(S30 | S32 | S50)]
+    C39,
+    #[compound(S22 | S37 | S62)]
+    C40,
+    #[compound(S16 | S29)]
+    C41,
+    #[compound(S9 | S27 | S35)]
+    C42,
+    #[compound(S2 | S8)]
+    C43,
+    #[compound(S31 | S52 | S57)]
+    C44,
+    #[compound(S5 | S22)]
+    C45,
+    #[compound(S11 | S16 | S47)]
+    C46,
+    #[compound(S17 | S42 | S57)]
+    C47,
+    #[compound(S2 | S4)]
+    C48,
+    #[compound(S4 | S39 | S45)]
+    C49,
+    #[compound(S9 | S61)]
+    C50,
+    #[compound(S39 | S40)]
+    C51,
+    #[compound(S9 | S57)]
+    C52,
+    #[compound(S5 | S16 | S43)]
+    C53,
+    #[compound(S9 | S10 | S60)]
+    C54,
+    #[compound(S1 | S3 | S63)]
+    C55,
+    #[compound(S1 | S9 | S48)]
+    C56,
+    #[compound(S11 | S14)]
+    C57,
+    #[compound(S42 | S49 | S53)]
+    C58,
+    #[compound(S10 | S56 | S59)]
+    C59,
+    #[compound(S11 | S39)]
+    C60,
+    #[compound(S2 | S14 | S29)]
+    C61,
+    #[compound(S1 | S32 | S62)]
+    C62,
+    #[compound(S18 | S25 | S38)]
+    C63,
+    #[compound(S43 | S56)]
+    C64,
+    #[compound(S30 | S41 | S51)]
+    C65,
+    #[compound(S25 | S27 | S55)]
+    C66,
+    #[compound(S26 | S28 | S40)]
+    C67,
+    #[compound(S17 | S63)]
+    C68,
+    #[compound(S5 | S8 | S35)]
+    C69,
+    #[compound(S14 | S57)]
+    C70,
+    #[compound(S27 | S35 | S52)]
+    C71,
+    #[compound(S40 | S57 | S63)]
+    C72,
+    #[compound(S4 | S9 | S35)]
+    C73,
+    #[compound(S35 | S45)]
+    C74,
+    #[compound(S2 | S17 | S51)]
+    C75,
+    #[compound(S3 | S24 | S34)]
+    C76,
+    #[compound(S6 | S18)]
+    C77,
+    #[compound(S13 | S57)]
+    C78,
+    #[compound(S9 | S25 | S60)]
+    C79,
+}
+
+fn decompose<T: Copy>(flags: impl Iterator<Item = T>) -> Vec<T> {
+    flags.collect()
+}
+
+#[test]
+fn prefers_compounds() {
+    let (flags, unknown) = (Permissions::Read | Permissions::Write).decompose();
+    assert!(matches!(decompose(flags)[..], [Permissions::ReadWrite]));
+    assert_eq!(unknown, PermissionsBits::EMPTY);
+
+    let (flags, _) = (Permissions::ReadWrite | Permissions::Exec).decompose();
+    assert!(matches!(
+        decompose(flags)[..],
+        [Permissions::ReadWrite, Permissions::Exec]
+    ));
+}
+
+#[test]
+fn returns_the_unknown_bits() {
+    let (flags, unknown) = PermissionsBits::from_bits_retain(0x84).decompose();
+    assert!(matches!(decompose(flags)[..], [Permissions::Exec]));
+    assert_eq!(unknown, PermissionsBits::from_bits_retain(0x80));
+
+    let (flags, unknown) = PermissionsBits::EMPTY.decompose();
+    assert_eq!(flags.count(), 0);
+    assert_eq!(unknown, PermissionsBits::EMPTY);
+}
+
+#[test]
+fn finds_the_smallest_cover_of_overlapping_compounds() {
+    let (flags, unknown) = OverlappingBits::ALL.decompose();
+    assert!(matches!(
+        decompose(flags)[..],
+        [Overlapping::Low, Overlapping::High]
+    ));
+    assert_eq!(unknown, OverlappingBits::EMPTY);
+
+    // Without `B6`, `High` is not contained and `Middle` is needed
+    let (flags, _) = (OverlappingBits::ALL & !OverlappingBits::from(Overlapping::B6)).decompose();
+    assert!(matches!(
+        decompose(flags)[..],
+        [Overlapping::Middle, Overlapping::Low]
+    ));
+}
+
+#[test]
+fn finds_the_smallest_cover_of_every_value() {
+    for raw in 0..=0b11_1111u8 {
+        let (flags, unknown) = OverlappingBits::from_bits_retain(raw).decompose();
+        let flags = decompose(flags);
+        assert_eq!(unknown, OverlappingBits::EMPTY);
+        assert_eq!(flags.iter().fold(0, |bits, &flag| bits | flag as u8), raw);
+
+        // Every combination of the flags contained in `raw` that covers it
+        let smallest = (0..1u32 << Overlapping::COUNT)
+            .filter_map(|subset| {
+                let picked = Overlapping::VARIANTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| subset & 1 << i != 0)
+                    .map(|(_, &flag)| flag as u8);
+                let union = picked.clone().try_fold(0, |union, bits| {
+                    (raw & bits == bits).then_some(union | bits)
+                })?;
+                (union == raw).then_some(picked.count())
+            })
+            .min();
+        assert_eq!(Some(flags.len()), smallest, "{raw:#08b}");
+    }
+}
+
+#[test]
+fn bounds_the_search() {
+    let (flags, unknown) = TangledBits::ALL.decompose();
+    let flags = decompose(flags);
+    assert_eq!(unknown, TangledBits::EMPTY);
+    assert_eq!(
+        flags.iter().fold(0, |bits, &flag| bits | flag as u64),
+        u64::from(TangledBits::ALL)
+    );
+
+    // Never larger than picking the widest flag covering new bits
+    let mut widest_first = Tangled::VARIANTS;
+    widest_first.sort_by_key(|&flag| core::cmp::Reverse((flag as u64).count_ones()));
+    let mut uncovered = u64::from(TangledBits::ALL);
+    let mut greedy = 0;
+    for flag in widest_first {
+        if uncovered & flag as u64 != 0 {
+            uncovered &= !(flag as u64);
+            greedy += 1;
+        }
+    }
+    assert!(flags.len() <= greedy);
+}
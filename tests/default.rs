@@ -0,0 +1,36 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask(default = Read | Write)]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+}
+
+// The bare form from before `default` took a value is still accepted
+#[bitmask(default, enable_auto_assign)]
+#[repr(u8)]
+pub enum Legacy {
+    Read,
+    Write,
+}
+
+#[test]
+fn defaults_to_the_expression() {
+    assert_eq!(
+        PermissionsBits::DEFAULT,
+        Permissions::Read | Permissions::Write
+    );
+    assert_eq!(PermissionsBits::default(), PermissionsBits::DEFAULT);
+
+    let mut bits = PermissionsBits::from(Permissions::Exec);
+    bits.reset();
+    assert_eq!(bits, PermissionsBits::DEFAULT);
+}
+
+#[test]
+fn accepts_a_bare_default() {
+    assert_eq!(Legacy::Write as u8, 0b10);
+    assert_eq!(LegacyBits::ALL, Legacy::Read | Legacy::Write);
+}
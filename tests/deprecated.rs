@@ -0,0 +1,52 @@
+#![allow(deprecated)]
+
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Retired {
+    Read = 0b001,
+    Write = 0b010,
+    #[deprecated]
+    Old = 0b100,
+}
+
+#[bitmask(include_deprecated)]
+#[repr(u8)]
+pub enum Kept {
+    Read = 0b001,
+    Write = 0b010,
+    #[deprecated]
+    Old = 0b100,
+}
+
+#[test]
+fn deprecated_names_are_not_parsed_by_default() {
+    assert_eq!(Retired::try_from("Old").ok(), None);
+    assert_eq!(Retired::from_name("Old"), None);
+    assert!("Read | Old".parse::<RetiredBits>().is_err());
+    assert!(RetiredBits::from_names(["Old"]).is_err());
+
+    let (bits, unknown) = RetiredBits::from_str_lossy("Read | Old");
+    assert_eq!(bits, RetiredBits::from(Retired::Read));
+    assert_eq!(unknown.collect::<Vec<_>>(), ["Old"]);
+
+    // The raw value still parses
+    assert_eq!(
+        "0x4".parse::<RetiredBits>(),
+        Ok(RetiredBits::from(Retired::Old))
+    );
+    assert_eq!(Retired::from_name("Write"), Some(Retired::Write));
+}
+
+#[test]
+fn include_deprecated_parses_deprecated_names() {
+    assert_eq!(Kept::try_from("Old").ok(), Some(Kept::Old));
+    assert_eq!(Kept::from_name("Old"), Some(Kept::Old));
+    assert_eq!("Read | Old".parse::<KeptBits>(), Ok(Kept::Read | Kept::Old));
+    assert_eq!(KeptBits::from_names(["Old"]), Ok(KeptBits::from(Kept::Old)));
+
+    let (bits, unknown) = KeptBits::from_str_lossy("Read | Old");
+    assert_eq!(bits, Kept::Read | Kept::Old);
+    assert_eq!(unknown.count(), 0);
+}
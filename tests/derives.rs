@@ -0,0 +1,29 @@
+use enum_bitmasks::bitmask;
+
+// Derives of the traits the macro derives itself go below it, where it sees
+// them, and derives of other traits can go on either side
+#[derive(Default)]
+#[bitmask]
+#[derive(Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum Mode {
+    #[default]
+    Read = 1,
+    Write = 2,
+}
+
+#[bitmask]
+#[derive(PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Access {
+    Read = 1,
+    Write = 2,
+}
+
+#[test]
+fn keeps_derives_below_the_macro() {
+    assert_eq!(format!("{:?}", Mode::Write), "Write");
+    assert_eq!(Mode::default(), Mode::Read);
+    assert_eq!(Access::Read, Access::Read);
+    assert!(Access::Read < Access::Write);
+}
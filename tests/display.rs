@@ -0,0 +1,22 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b01,
+    Write = 0b10,
+}
+
+#[test]
+fn round_trips_unknown_bits() {
+    let value = PermissionsBits::from_bits_retain(0x81);
+    assert_eq!(value.to_string(), "Read | 0x80");
+    assert_eq!("Read | 0x80".parse::<PermissionsBits>().unwrap(), value);
+}
+
+#[test]
+fn returns_the_parts_that_do_not_parse() {
+    let (value, unknown) = PermissionsBits::from_str_lossy("Read | Fly");
+    assert_eq!(value, PermissionsBits::from(Permissions::Read));
+    assert!(unknown.eq(["Fly"]));
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "enumflags2")]
+
+use enum_bitmasks::bitmask;
+use enumflags2::{BitFlag, BitFlags};
+
+#[bitmask(enumflags2, default = Read)]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+}
+
+#[test]
+fn is_a_bit_flag() {
+    let flags: BitFlags<Permissions> = BitFlags::from(Permissions::Read) | Permissions::Exec;
+    assert_eq!(flags.bits(), 0b101);
+    assert!(flags.contains(Permissions::Exec));
+    assert_eq!(Permissions::all().bits(), 0b111);
+    assert_eq!(BitFlags::<Permissions>::default(), Permissions::Read);
+    assert_eq!(
+        flags.iter().collect::<Vec<_>>(),
+        [Permissions::Read, Permissions::Exec]
+    );
+}
+
+#[test]
+fn converts_to_and_from_the_bits_type() {
+    let bits = Permissions::Write | Permissions::Exec;
+    let flags = BitFlags::<Permissions>::try_from(bits).unwrap();
+    assert_eq!(
+        flags,
+        BitFlags::from(Permissions::Write) | Permissions::Exec
+    );
+    assert_eq!(PermissionsBits::from(flags), bits);
+
+    assert!(BitFlags::<Permissions>::try_from(PermissionsBits::from_bits_retain(0x08)).is_err());
+}
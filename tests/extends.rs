@@ -0,0 +1,44 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Base {
+    Read = 0b01,
+    Write = 0b10,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[bitmask(extends = Base, enable_auto_assign)]
+#[repr(u16)]
+pub enum Extended {
+    Exec,
+    #[compound(Read | Exec)]
+    ReadExec,
+}
+
+#[test]
+fn inherits_names_and_values() {
+    assert_eq!(Extended::Read as u16, Base::Read as u16);
+    assert_eq!(Extended::ReadWrite as u16, Base::ReadWrite as u16);
+    // Auto-assigned after the highest inherited bit
+    assert_eq!(Extended::Exec as u16, 0b100);
+    assert_eq!(Extended::ReadExec as u16, 0b101);
+    assert_eq!(u16::from(ExtendedBits::ALL), 0b111);
+    assert_eq!(Extended::try_from("Write").ok(), Some(Extended::Write));
+}
+
+#[test]
+fn converts_between_base_and_extended() {
+    assert_eq!(Extended::from(Base::Write), Extended::Write);
+    assert_eq!(
+        ExtendedBits::from(BaseBits::from(Base::ReadWrite)),
+        ExtendedBits::from(Extended::ReadWrite)
+    );
+
+    assert_eq!(
+        BaseBits::try_from(Extended::Read | Extended::Write),
+        Ok(BaseBits::from(Base::ReadWrite))
+    );
+    assert!(BaseBits::try_from(Extended::Read | Extended::Exec).is_err());
+}
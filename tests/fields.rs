@@ -0,0 +1,22 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b01,
+    WriteAll = 0b10,
+    #[compound(Read | WriteAll)]
+    ReadWrite,
+}
+
+#[test]
+fn converts_to_and_from_the_struct() {
+    let mut fields = PermissionsBits::from(Permissions::Read).to_struct();
+    assert!(fields.read && !fields.write_all);
+
+    fields.write_all = true;
+    assert_eq!(
+        PermissionsBits::from_struct(fields),
+        PermissionsBits::from(Permissions::ReadWrite)
+    );
+}
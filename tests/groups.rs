@@ -0,0 +1,42 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    #[bitmask(group = "io")]
+    Read = 0b0001,
+    #[bitmask(group = "io")]
+    Write = 0b0010,
+    Exec = 0b0100,
+    #[bitmask(skip, group = "io")]
+    Legacy = 0b1000,
+    #[bitmask(skip, group = "internal")]
+    Sentinel = 0b1_0000,
+}
+
+#[test]
+fn leaves_skipped_variants_out_of_groups() {
+    assert_eq!(
+        PermissionsBits::GROUP_IO,
+        Permissions::Read | Permissions::Write
+    );
+    assert_eq!(PermissionsBits::GROUP_INTERNAL, PermissionsBits::EMPTY);
+
+    let bits = Permissions::Write | Permissions::Legacy;
+    assert!(bits.io_flags().eq([Permissions::Write]));
+    assert!(!PermissionsBits::from(Permissions::Legacy).has_io_flags());
+    assert!(!PermissionsBits::from(Permissions::Sentinel).has_internal_flags());
+    assert_eq!(
+        PermissionsBits::from(Permissions::Sentinel)
+            .internal_flags()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn toggles_the_listed_flags_of_a_group() {
+    let mut bits = Permissions::Read | Permissions::Legacy;
+    bits.toggle_io_flags();
+    assert_eq!(bits, Permissions::Write | Permissions::Legacy);
+}
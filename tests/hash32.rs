@@ -0,0 +1,37 @@
+#![cfg(feature = "hash32")]
+
+use enum_bitmasks::bitmask;
+use hash32::{FnvHasher, Hash, Hasher};
+
+#[bitmask]
+#[repr(u128)]
+pub enum Permissions {
+    Read = 1,
+    Write = 2,
+    Exec = 1 << 100,
+}
+
+fn fnv(value: &impl Hash) -> u32 {
+    let mut hasher = FnvHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn hashes_the_value() {
+    let bits = Permissions::Read | Permissions::Exec;
+    assert_eq!(fnv(&bits), fnv(&(Permissions::Read | Permissions::Exec)));
+    assert_ne!(fnv(&bits), fnv(&PermissionsBits::from(Permissions::Read)));
+
+    let mut hasher = FnvHasher::default();
+    hasher.write(&u128::from(bits).to_ne_bytes());
+    assert_eq!(fnv(&bits), hasher.finish());
+}
+
+#[test]
+fn flags_hash_like_their_bits() {
+    assert_eq!(
+        fnv(&Permissions::Exec),
+        fnv(&PermissionsBits::from(Permissions::Exec))
+    );
+}
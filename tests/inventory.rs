@@ -0,0 +1,55 @@
+#![cfg(feature = "inventory")]
+
+use enum_bitmasks::bitmask;
+
+pub struct BitmaskInfo {
+    name: &'static str,
+    bits: u32,
+    flags: &'static [(&'static str, u128)],
+}
+
+impl BitmaskInfo {
+    pub const fn new(
+        name: &'static str,
+        bits: u32,
+        flags: &'static [(&'static str, u128)],
+    ) -> Self {
+        Self { name, bits, flags }
+    }
+}
+
+inventory::collect!(BitmaskInfo);
+
+#[bitmask(register = BitmaskInfo)]
+#[repr(u16)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    #[compound(Read | Write)]
+    ReadWrite,
+    #[bitmask(skip)]
+    Internal = 0x100,
+}
+
+#[bitmask(register = BitmaskInfo)]
+#[repr(u8)]
+pub enum Mode {
+    Fast = 1,
+}
+
+#[test]
+fn registers_every_flag_table() {
+    let mut names: Vec<_> = inventory::iter::<BitmaskInfo>
+        .into_iter()
+        .map(|i| i.name)
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, ["Mode", "Permissions"]);
+
+    let info = inventory::iter::<BitmaskInfo>
+        .into_iter()
+        .find(|i| i.name == "Permissions")
+        .unwrap();
+    assert_eq!(info.bits, 16);
+    assert_eq!(info.flags, [("Read", 1), ("Write", 2), ("ReadWrite", 3)]);
+}
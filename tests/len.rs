@@ -0,0 +1,45 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b0001,
+    Write = 0b0010,
+    Exec = 0b0100,
+    #[compound(Read | Write)]
+    ReadWrite,
+    #[bitmask(skip)]
+    Reserved = 0b1000,
+}
+
+#[test]
+fn counts_single_bit_flags() {
+    assert_eq!(PermissionsBits::EMPTY.len(), 0);
+    assert_eq!(PermissionsBits::EMPTY.count_ones(), 0);
+
+    let bits = Permissions::Read | Permissions::Exec;
+    assert_eq!(bits.len(), 2);
+    assert_eq!(bits.count_ones(), 2);
+}
+
+#[test]
+fn counts_the_flags_of_compounds() {
+    let bits = PermissionsBits::from(Permissions::ReadWrite);
+    assert_eq!(bits.len(), 2);
+    assert_eq!(bits.count_ones(), 2);
+
+    let bits = Permissions::ReadWrite | Permissions::Exec;
+    assert_eq!(bits.len(), 3);
+    assert_eq!(bits.len(), PermissionsBits::ALL.len());
+}
+
+#[test]
+fn leaves_out_skipped_and_unknown_bits() {
+    let bits = Permissions::Read | Permissions::Reserved;
+    assert_eq!(bits.len(), 1);
+    assert_eq!(bits.count_ones(), 2);
+
+    let bits = PermissionsBits::from_bits_retain(0b1111_0010);
+    assert_eq!(bits.len(), 1);
+    assert_eq!(bits.count_ones(), 5);
+}
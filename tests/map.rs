@@ -0,0 +1,38 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    #[compound(Read | Write)]
+    ReadWrite,
+    #[bitmask(skip)]
+    Reserved = 0b100,
+}
+
+#[test]
+fn has_a_slot_for_every_variant() {
+    let map = PermissionsMap::from_array(["read", "write", "read-write", "reserved"]);
+    assert_eq!(
+        map.iter().map(|(flag, _)| flag).collect::<Vec<_>>(),
+        [
+            Permissions::Read,
+            Permissions::Write,
+            Permissions::ReadWrite,
+            Permissions::Reserved
+        ]
+    );
+    assert_eq!(map[Permissions::Reserved], "reserved");
+}
+
+#[test]
+fn keeps_compounds_apart_from_their_flags() {
+    let mut costs = PermissionsMap::<u32>::default();
+    costs[Permissions::ReadWrite] = 3;
+    assert_eq!(costs[Permissions::Read], 0);
+    assert_eq!(costs[Permissions::Write], 0);
+
+    costs[Permissions::Read] = 1;
+    assert_eq!(costs[Permissions::ReadWrite], 3);
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "modular-bitfield")]
+
+use enum_bitmasks::bitmask;
+use modular_bitfield::{error::OutOfBounds, prelude::*};
+
+#[bitmask]
+#[repr(u16)]
+pub enum Permissions {
+    Read = 0b0001,
+    Write = 0b0010,
+    Exec = 0b0100,
+    #[bitmask(skip)]
+    Reserved = 0b1_0000,
+}
+
+#[bitmask]
+#[repr(usize)]
+pub enum Mode {
+    Fast = 0b01,
+    Safe = 0b10,
+}
+
+#[bitfield]
+struct Register {
+    permissions: PermissionsBits,
+    rest: B3,
+}
+
+#[bitfield]
+struct Control {
+    mode: ModeBits,
+    level: B6,
+}
+
+#[test]
+fn is_as_wide_as_the_highest_bit() {
+    assert_eq!(<PermissionsBits as Specifier>::BITS, 5);
+}
+
+#[test]
+fn packs_into_a_bitfield() {
+    let mut register = Register::new();
+    register.set_permissions(Permissions::Read | Permissions::Exec);
+    register.set_rest(0b111);
+    assert_eq!(
+        register.permissions(),
+        Permissions::Read | Permissions::Exec
+    );
+    assert_eq!(register.rest(), 0b111);
+    assert_eq!(register.into_bytes(), [0b1110_0101]);
+}
+
+#[test]
+fn rejects_bits_beyond_the_field() {
+    let mut register = Register::new();
+    assert_eq!(
+        register.set_permissions_checked(PermissionsBits::from_bits_retain(0b10_0000)),
+        Err(OutOfBounds)
+    );
+
+    // Bits within the field that belong to no flag are kept
+    let register = Register::from_bytes([0b0000_1000]);
+    assert_eq!(u16::from(register.permissions()), 0b1000);
+}
+
+#[test]
+fn packs_usize_representations() {
+    assert_eq!(<ModeBits as Specifier>::BITS, 2);
+
+    let mut control = Control::new();
+    control.set_mode(Mode::Safe.into());
+    control.set_level(0b10_1010);
+    assert_eq!(control.mode(), ModeBits::from(Mode::Safe));
+    assert_eq!(control.into_bytes(), [0b1010_1010]);
+
+    let control = Control::from_bytes([0b0000_0101]);
+    assert_eq!(control.mode(), ModeBits::from(Mode::Fast));
+    assert_eq!(control.level(), 0b1);
+}
@@ -0,0 +1,101 @@
+//! Checks that the generated methods cannot panic, by failing to link when a
+//! panic path survives optimization. Parsing and formatting are left out, as
+//! the optimizer cannot see through core's string and formatting code. Only
+//! meaningful with optimizations:
+//!
+//! ```text
+//! cargo test --release --features no_panic_test --test no_panic
+//! ```
+#![cfg(all(feature = "no_panic_test", not(debug_assertions)))]
+
+use core::hint::black_box;
+
+use enum_bitmasks::bitmask;
+
+#[bitmask(mask = 0xff, default = Read | Write)]
+#[repr(u16)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        unsafe extern "C" {
+            // Never defined, so linking fails if this call is not optimized out
+            fn generated_method_may_panic() -> !;
+        }
+        unsafe { generated_method_may_panic() }
+    }
+}
+
+#[inline(always)]
+fn no_panic<T>(f: impl FnOnce() -> T) {
+    let guard = Guard;
+    black_box(f());
+    core::mem::forget(guard);
+}
+
+#[test]
+fn generated_methods_do_not_panic() {
+    let raw: u16 = black_box(0x8105);
+    let bits = PermissionsBits::from_bits_retain(raw);
+    let flag = black_box(Permissions::Write);
+
+    no_panic(|| PermissionsBits::try_from(raw));
+    no_panic(|| PermissionsBits::from_bits(raw));
+    no_panic(|| PermissionsBits::from_bits_truncate(raw));
+    no_panic(|| u16::from(bits));
+    no_panic(|| PermissionsBits::from(flag));
+    let other = PermissionsBits::from(flag);
+    no_panic(|| (bits | other, bits & other, bits ^ other, !bits));
+    no_panic(|| (flag | Permissions::Read, !flag, &flag | &flag));
+    no_panic(|| {
+        let mut bits = bits;
+        bits |= flag;
+        bits -= &flag;
+        bits ^= PermissionsBits::ALL;
+        bits.insert(Permissions::Read);
+        bits.remove(flag);
+        bits.toggle(other);
+        bits.set(flag, black_box(true));
+        bits &= PermissionsBits::DEFAULT;
+        bits
+    });
+    no_panic(|| {
+        (
+            bits.contains(flag),
+            bits.intersects(other),
+            bits.is_empty(),
+            bits.is_all(),
+        )
+    });
+    no_panic(|| bits.to_positions().sum::<u32>());
+    no_panic(|| bits.iter().count());
+    no_panic(|| {
+        let (flags, unknown) = bits.decompose();
+        (flags.count(), unknown)
+    });
+    no_panic(|| PermissionsBits::from_positions(black_box([3, 40])));
+    no_panic(|| PermissionsBits::from_struct(bits.to_struct()));
+    no_panic(|| (bits.to_c_uint(), PermissionsBits::from_c_ulong(7)));
+    no_panic(|| PermissionsBits::from_u128_truncate(bits.bits_u128() << 9));
+    no_panic(|| {
+        let mut count = 0;
+        bits.for_each_flag(|_| count += 1);
+        count
+    });
+    no_panic(|| {
+        let mut map = PermissionsMap::from_array([1u8, 2, 3, 4]);
+        map[flag] += 1;
+        *map.get(Permissions::Exec)
+    });
+    no_panic(|| Permissions::try_from(black_box("Exec")));
+    no_panic(|| Permissions::from_repr(raw));
+    no_panic(|| Permissions::try_from_bit_index(black_box(40)));
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "prost")]
+
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    // Named like the associated type of `TryFrom`
+    Error = 0b100,
+}
+
+#[bitmask]
+#[repr(u64)]
+pub enum Wide {
+    Low = 1,
+    High = 1 << 40,
+}
+
+#[test]
+fn enum_round_trips_through_i32() {
+    assert_eq!(i32::from(Permissions::Error), 4);
+    assert_eq!(Permissions::try_from(2), Ok(Permissions::Write));
+    assert_eq!(Permissions::try_from(3), Err(prost::UnknownEnumValue(3)));
+}
+
+#[test]
+fn bits_convert_to_u32() {
+    assert_eq!(u32::from(Permissions::Read | Permissions::Error), 0b101);
+}
+
+#[test]
+fn from_proto_lossy_splits_unknown_bits() {
+    assert_eq!(
+        PermissionsBits::from_proto_lossy(0x103),
+        (Permissions::Read | Permissions::Write, 0x100)
+    );
+    assert_eq!(
+        WideBits::from_proto_lossy(0b11),
+        (WideBits::from(Wide::Low), 0b10)
+    );
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "pyo3")]
+
+use enum_bitmasks::bitmask;
+use pyo3::{IntoPyObject, Python, exceptions::PyValueError, types::PyAnyMethods};
+
+#[bitmask]
+#[repr(u16)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[test]
+fn round_trips_through_python_ints() {
+    Python::attach(|py| {
+        let bits = Permissions::Read | Permissions::Exec;
+        let int = bits.into_pyobject(py).unwrap();
+        assert_eq!(int.extract::<u16>().unwrap(), 5);
+        assert_eq!(int.extract::<PermissionsBits>().unwrap(), bits);
+
+        let int = 0b011u16.into_pyobject(py).unwrap();
+        assert_eq!(
+            int.extract::<PermissionsBits>().unwrap(),
+            PermissionsBits::from(Permissions::ReadWrite)
+        );
+    });
+}
+
+#[test]
+fn rejects_undefined_bits() {
+    Python::attach(|py| {
+        let int = 0b1000u16.into_pyobject(py).unwrap();
+        let err = int.extract::<PermissionsBits>().unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+}
+
+#[test]
+fn creates_an_int_flag_class() {
+    Python::attach(|py| {
+        let class = PermissionsBits::py_flag_class(py).unwrap();
+        assert_eq!(
+            class
+                .getattr("__name__")
+                .unwrap()
+                .extract::<String>()
+                .unwrap(),
+            "Permissions"
+        );
+
+        let read_write = class.getattr("ReadWrite").unwrap();
+        assert_eq!(read_write.extract::<u16>().unwrap(), 0b011);
+        let combined = class
+            .getattr("Read")
+            .unwrap()
+            .call_method1("__or__", (class.getattr("Write").unwrap(),))
+            .unwrap();
+        assert!(combined.eq(&read_write).unwrap());
+    });
+}
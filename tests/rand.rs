@@ -0,0 +1,50 @@
+#![cfg(feature = "rand")]
+
+use enum_bitmasks::bitmask;
+use rand::{SeedableRng, rngs::SmallRng};
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[bitmask]
+#[repr(u128)]
+pub enum Wide {
+    Low = 1,
+    High = 1 << 127,
+}
+
+#[test]
+fn sample_defined_is_uniform() {
+    let mut rng = SmallRng::seed_from_u64(7);
+    let mut counts = [0u32; 8];
+    for _ in 0..80_000 {
+        let bits = PermissionsBits::sample_defined(&mut rng);
+        assert_eq!(bits.unknown_bits(), PermissionsBits::EMPTY);
+        counts[u8::from(bits) as usize] += 1;
+    }
+
+    // Each of the 8 values is expected 10_000 times, with a standard
+    // deviation below 100
+    for count in counts {
+        assert!((9_500..10_500).contains(&count), "{counts:?}");
+    }
+}
+
+#[test]
+fn sample_defined_covers_wide_representations() {
+    let mut rng = SmallRng::seed_from_u64(7);
+    let mut seen = [0u32; 4];
+    for _ in 0..400 {
+        let bits = u128::from(WideBits::sample_defined(&mut rng));
+        assert_eq!(bits & !(1 | 1 << 127), 0);
+        seen[(bits & 1 | bits >> 126) as usize] += 1;
+    }
+    assert!(seen.iter().all(|&n| n > 50), "{seen:?}");
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "redis")]
+
+use enum_bitmasks::bitmask;
+use redis::{FromRedisValue, ToRedisArgs, Value};
+
+#[bitmask]
+#[repr(u16)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+}
+
+#[test]
+fn writes_the_raw_integer() {
+    let bits = Permissions::Read | Permissions::Exec;
+    assert_eq!(bits.to_redis_args(), 5u16.to_redis_args());
+}
+
+#[test]
+fn reads_defined_bits_only() {
+    assert_eq!(
+        PermissionsBits::from_redis_value(Value::Int(3)).ok(),
+        Some(Permissions::Read | Permissions::Write)
+    );
+    assert_eq!(
+        PermissionsBits::from_redis_value(Value::BulkString(b"6".to_vec())).ok(),
+        Some(Permissions::Write | Permissions::Exec)
+    );
+    assert!(PermissionsBits::from_redis_value(Value::Int(8)).is_err());
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "serde")]
+
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[test]
+fn writes_the_names_of_display() {
+    let bits = PermissionsBits::from_bits_retain(0x83);
+    assert_eq!(bits.to_string(), "Read | Write | ReadWrite | 0x80");
+    assert_eq!(
+        serde_json::to_string(&bits).unwrap(),
+        r#"["Read","Write","ReadWrite","0x80"]"#
+    );
+    assert_eq!(
+        serde_json::to_string(&PermissionsBits::EMPTY).unwrap(),
+        "[]"
+    );
+}
+
+#[test]
+fn round_trips_unknown_bits() {
+    let bits = PermissionsBits::from_bits_retain(0x85);
+    let json = serde_json::to_string(&bits).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PermissionsBits>(&json).unwrap(),
+        bits
+    );
+    assert_eq!(
+        serde_json::from_str::<PermissionsBits>(r#""Read | Exec""#).unwrap(),
+        Permissions::Read | Permissions::Exec
+    );
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "strum")]
+
+use enum_bitmasks::bitmask;
+use strum::{EnumCount, IntoEnumIterator, VariantNames};
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    #[compound(Read | Write)]
+    ReadWrite,
+    #[bitmask(skip)]
+    Sentinel = 0b100,
+}
+
+#[test]
+fn iterates_the_variants() {
+    assert!(Permissions::iter().eq(Permissions::VARIANTS));
+    assert_eq!(Permissions::iter().len(), 3);
+}
+
+#[test]
+fn counts_the_variants() {
+    assert_eq!(<Permissions as EnumCount>::COUNT, Permissions::COUNT);
+    assert_eq!(<Permissions as EnumCount>::COUNT, 3);
+}
+
+#[test]
+fn names_the_variants() {
+    for flag in Permissions::iter().chain([Permissions::Sentinel]) {
+        assert_eq!(<&'static str>::from(flag), flag.name());
+        assert_eq!(<&'static str>::from(&flag), flag.name());
+    }
+    assert_eq!(
+        <Permissions as VariantNames>::VARIANTS,
+        Permissions::VARIANT_NAMES
+    );
+}
+
+#[test]
+fn parses_listed_names() {
+    assert_eq!(
+        "ReadWrite".parse::<Permissions>(),
+        Ok(Permissions::ReadWrite)
+    );
+    assert_eq!(
+        "Sentinel".parse::<Permissions>(),
+        Err(strum::ParseError::VariantNotFound)
+    );
+}
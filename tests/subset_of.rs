@@ -0,0 +1,39 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u16)]
+pub enum Internal {
+    Read = 0x001,
+    Write = 0x002,
+    Trace = 0x100,
+}
+
+#[bitmask(subset_of = Internal)]
+#[repr(u16)]
+pub enum Public {
+    Read,
+    Write,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[test]
+fn takes_the_values_of_the_parent() {
+    assert_eq!(Public::Read as u16, Internal::Read as u16);
+    assert_eq!(Public::Write as u16, Internal::Write as u16);
+    assert_eq!(Public::ReadWrite as u16, 0x003);
+    assert_eq!(u16::from(PublicBits::ALL), 0x003);
+    assert!(PublicBits::try_from(Internal::Trace as u16).is_err());
+}
+
+#[test]
+fn converts_into_the_parent() {
+    assert_eq!(
+        InternalBits::from(Public::Write),
+        InternalBits::from(Internal::Write)
+    );
+    assert_eq!(
+        InternalBits::from(Public::Read | Public::Write),
+        Internal::Read | Internal::Write
+    );
+}
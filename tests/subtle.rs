@@ -0,0 +1,79 @@
+#![cfg(feature = "subtle")]
+
+use enum_bitmasks::bitmask;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[bitmask]
+#[repr(usize)]
+pub enum Wide {
+    Low = 1,
+    High = 1 << 20,
+}
+
+#[test]
+fn compares_in_constant_time() {
+    let read_write = PermissionsBits::from(Permissions::ReadWrite);
+    assert!(bool::from(read_write.ct_eq(&read_write)));
+    assert!(bool::from(
+        read_write.ct_eq(&(Permissions::Read | Permissions::Write))
+    ));
+    assert!(!bool::from(read_write.ct_eq(&Permissions::Read.into())));
+    assert!(!bool::from(
+        read_write.ct_eq(&(Permissions::Read | Permissions::Exec))
+    ));
+}
+
+#[test]
+fn selects_in_constant_time() {
+    let a = PermissionsBits::from(Permissions::ReadWrite);
+    let b = PermissionsBits::from(Permissions::Exec);
+    assert_eq!(
+        PermissionsBits::conditional_select(&a, &b, Choice::from(0)),
+        a
+    );
+    assert_eq!(
+        PermissionsBits::conditional_select(&a, &b, Choice::from(1)),
+        b
+    );
+    assert_eq!(
+        PermissionsBits::conditional_select(&a, &a, Choice::from(1)),
+        a
+    );
+
+    let low = WideBits::from(Wide::Low);
+    let all = WideBits::ALL;
+    assert_eq!(
+        WideBits::conditional_select(&low, &all, Choice::from(0)),
+        low
+    );
+    assert_eq!(
+        WideBits::conditional_select(&low, &all, Choice::from(1)),
+        all
+    );
+}
+
+#[test]
+fn checks_contained_bits_in_constant_time() {
+    let bits = Permissions::ReadWrite | Permissions::Exec;
+    assert!(bool::from(bits.ct_contains(Permissions::Read.into())));
+    assert!(bool::from(bits.ct_contains(Permissions::ReadWrite.into())));
+    assert!(!bool::from(
+        PermissionsBits::from(Permissions::Read).ct_contains(Permissions::ReadWrite.into())
+    ));
+    assert!(bool::from(
+        PermissionsBits::from(Permissions::Read).ct_intersects(Permissions::ReadWrite.into())
+    ));
+    assert!(!bool::from(
+        PermissionsBits::from(Permissions::Exec).ct_intersects(Permissions::ReadWrite.into())
+    ));
+}
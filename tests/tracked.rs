@@ -0,0 +1,20 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask(tracked)]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b01,
+    Write = 0b10,
+}
+
+#[test]
+fn reports_the_changes_since_the_last_call() {
+    let mut tracked = TrackedPermissionsBits::new(Permissions::Read.into());
+    tracked.insert(Permissions::Write);
+    tracked.remove(Permissions::Read);
+
+    let changes = tracked.take_changes();
+    assert_eq!(changes.inserted, Permissions::Write);
+    assert_eq!(changes.removed, Permissions::Read);
+    assert!(!tracked.has_changes());
+}
@@ -0,0 +1,31 @@
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+}
+
+// The low nibble is reserved for flags, the high nibble for other fields
+#[bitmask(mask = 0x0f)]
+#[repr(u8)]
+pub enum Status {
+    Ready = 0b001,
+    Busy = 0b010,
+}
+
+#[test]
+fn truncates_to_the_flags() {
+    let bits = PermissionsBits::from_bits_retain(0xf3);
+    assert_eq!(bits.truncate(), Permissions::Read | Permissions::Write);
+    assert_eq!(bits.truncate(), PermissionsBits::from_bits_truncate(0xf3));
+}
+
+#[test]
+fn truncates_to_the_mask() {
+    let bits = StatusBits::from_bits_retain(0xf5);
+    assert_eq!(u8::from(bits.truncate()), 0x05);
+    assert_eq!(bits.truncate(), StatusBits::from_bits_truncate(0xf5));
+    assert_eq!(StatusBits::from_bits(0x05), Some(bits.truncate()));
+}
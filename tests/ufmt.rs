@@ -0,0 +1,51 @@
+#![cfg(feature = "ufmt")]
+
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+fn udisplay(bits: PermissionsBits) -> String {
+    let mut out = String::new();
+    ufmt::uwrite!(out, "{}", bits).unwrap();
+    out
+}
+
+fn udebug(bits: PermissionsBits) -> String {
+    let mut out = String::new();
+    ufmt::uwrite!(out, "{:?}", bits).unwrap();
+    out
+}
+
+#[test]
+fn writes_like_display() {
+    let values = [
+        PermissionsBits::EMPTY,
+        Permissions::Exec.into(),
+        Permissions::ReadWrite | Permissions::Exec,
+        PermissionsBits::from_bits_retain(0x81),
+        PermissionsBits::from_bits_retain(0xf0),
+    ];
+    for bits in values {
+        assert_eq!(udisplay(bits), bits.to_string());
+    }
+}
+
+#[test]
+fn writes_like_debug() {
+    let values = [
+        PermissionsBits::EMPTY,
+        Permissions::Read | Permissions::Exec,
+        PermissionsBits::from_bits_retain(0x82),
+    ];
+    for bits in values {
+        assert_eq!(udebug(bits), format!("{bits:?}"));
+    }
+}
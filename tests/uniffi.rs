@@ -0,0 +1,42 @@
+#![cfg(feature = "uniffi")]
+
+use enum_bitmasks::bitmask;
+use uniffi::{Lift, Lower};
+
+uniffi::setup_scaffolding!();
+
+#[bitmask]
+#[repr(u32)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[test]
+fn lowers_to_the_representation() {
+    let bits = Permissions::Read | Permissions::Exec;
+    assert_eq!(<PermissionsBits as Lower<UniFfiTag>>::lower(bits), 5);
+}
+
+#[test]
+fn lifts_defined_bits_only() {
+    assert_eq!(
+        <PermissionsBits as Lift<UniFfiTag>>::try_lift(3).ok(),
+        Some(PermissionsBits::from(Permissions::ReadWrite))
+    );
+    assert!(<PermissionsBits as Lift<UniFfiTag>>::try_lift(8).is_err());
+}
+
+#[test]
+fn exports_the_flags_by_name() {
+    let flags = permissions_flags();
+    assert_eq!(flags.len(), 4);
+    assert_eq!(flags["Exec"], PermissionsBits::from(Permissions::Exec));
+    assert_eq!(
+        flags["ReadWrite"],
+        PermissionsBits::from(Permissions::ReadWrite)
+    );
+}
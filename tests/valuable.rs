@@ -0,0 +1,40 @@
+#![cfg(feature = "valuable")]
+
+use enum_bitmasks::bitmask;
+use valuable::{Listable, Valuable, Value, Visit};
+
+#[bitmask]
+#[repr(u8)]
+pub enum Permissions {
+    Read = 0b001,
+    Write = 0b010,
+    Exec = 0b100,
+    #[compound(Read | Write)]
+    ReadWrite,
+}
+
+#[derive(Default)]
+struct Names(Vec<String>);
+
+impl Visit for Names {
+    fn visit_value(&mut self, value: Value<'_>) {
+        if let Value::String(name) = value {
+            self.0.push(name.to_owned());
+        }
+    }
+}
+
+#[test]
+fn lists_the_names_of_iter_names() {
+    let bits = PermissionsBits::from_bits_retain(0x83);
+    let mut names = Names::default();
+    bits.visit(&mut names);
+
+    let expected: Vec<_> = bits.iter_names().map(|(name, _)| name).collect();
+    assert_eq!(names.0, expected);
+    assert_eq!(bits.size_hint(), (3, Some(3)));
+    assert!(matches!(
+        Permissions::Exec.as_value(),
+        Value::String("Exec")
+    ));
+}
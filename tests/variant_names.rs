@@ -0,0 +1,25 @@
+//! Variants named like the associated types of the traits implemented for
+//! the enum must not make `Self::Error` and friends ambiguous.
+
+#![allow(clippy::op_ref)]
+
+use enum_bitmasks::bitmask;
+
+#[bitmask]
+#[repr(u8)]
+pub enum Pin {
+    Input = 0b0001,
+    Output = 0b0010,
+    Error = 0b0100,
+    Err = 0b1000,
+}
+
+#[test]
+fn variants_named_like_associated_types() {
+    assert_eq!(Pin::try_from("Error").ok(), Some(Pin::Error));
+    assert!(Pin::try_from("Warning").is_err());
+    assert_eq!("Output | Err".parse(), Ok(Pin::Output | Pin::Err));
+    assert_eq!(&Pin::Output & Pin::Output, PinBits::from(Pin::Output));
+    assert_eq!(!&Pin::Input, !PinBits::from(Pin::Input));
+    assert_eq!(Pin::Error ^ Pin::Err, Pin::Err | &Pin::Error);
+}